@@ -1,30 +1,51 @@
 use colored::Colorize;
 use regress::{Flags, Regex};
 use std::{
+    collections::BTreeMap,
     error::Error,
     fmt::{Debug, Display},
     fs::{self, File},
-    io::Write,
+    io::{self, Write},
     path::PathBuf,
 };
 
 use crate::{
-    config::Config,
-    github::ProjectsClient,
+    cache::CacheMode,
+    config::{CardSchema, Config, NumberingMode},
+    graph::DependencyGraph,
     image_cropping::crop_image,
-    lucid::LucidClient,
-    parsing::{sort_by_section, PldCard},
+    lucid::{LucidClient, LucidError},
+    numbering::{number_order, Numbering},
+    parsing::{print_report, sort_by_section, CardDiagnostics, Completion, PldCard},
+    source::{
+        self,
+        card::ProjectCard,
+        filter::{Filter, FilterError},
+        CardSource, MergedCards, SourceError,
+    },
 };
 
 // Tags
 const LUCID_TAG: &str = "{{lucid}}";
 const CARDS_TAG: &str = "{{cards}}";
 const TOC_TAG: &str = "{{table_of_contents}}";
+const DATE_TAG: &str = "{{date_now}}";
+const DOD_COMPLETION_TAG: &str = "{{dod_completion}}";
+const CRITICAL_PATH_TAG: &str = "{{critical_path}}";
+
+const DATE_RECORDING: &str = "date.txt";
+/// Card numbers kept across runs, in the output directory
+const NUMBERING_FILE: &str = "numbering.json";
 
 #[derive(Debug)]
 pub enum GeneratorError {
+    Cache(io::Error),
+    InvalidFilter(FilterError),
     InvalidOutputDirectory,
+    Lucid(LucidError),
     LucidInvalidRefreshToken,
+    NumberingFailed,
+    Source(SourceError),
     TemplateError,
     WriteFailed,
 }
@@ -32,12 +53,26 @@ pub enum GeneratorError {
 impl Display for GeneratorError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            GeneratorError::Cache(e) => write!(
+                f,
+                "Reading or writing the recorded responses has failed : {}",
+                e
+            )
+            .unwrap(),
+            GeneratorError::InvalidFilter(e) => write!(f, "{}", e).unwrap(),
             GeneratorError::InvalidOutputDirectory => {
                 write!(f, "Accessing or creating specified output directory").unwrap()
             }
+            GeneratorError::Lucid(e) => write!(f, "{}", e).unwrap(),
             GeneratorError::LucidInvalidRefreshToken => {
                 write!(f, "The specified lucid refresh token is invalid").unwrap()
             }
+            GeneratorError::NumberingFailed => write!(
+                f,
+                "Reading or writing the card numbers of the output directory has failed"
+            )
+            .unwrap(),
+            GeneratorError::Source(e) => write!(f, "{}", e).unwrap(),
             GeneratorError::TemplateError => write!(f, "The template could not be found").unwrap(),
             GeneratorError::WriteFailed => {
                 write!(f, "Writing to the specified output file has failed").unwrap()
@@ -50,17 +85,40 @@ impl Display for GeneratorError {
 
 impl Error for GeneratorError {}
 
+/// Parses the card filter, the command line one takes precedence over the config one
+pub fn card_filter(
+    conf: &Config,
+    filter: Option<String>,
+) -> Result<Option<Filter>, GeneratorError> {
+    filter
+        .or_else(|| conf.doc.as_ref().and_then(|d| d.filter.clone()))
+        .map(|expression| expression.parse())
+        .transpose()
+        .map_err(GeneratorError::InvalidFilter)
+}
+
 pub struct App {
     output_dir: String,
     output_file: File,
     output_buffer: String,
+    card_template: Option<String>,
+    filter: Option<Filter>,
+    card_schema: CardSchema,
+    /// Parsing errors of the cards left out of the PLD, reported at the end of the run
+    diagnostics: Vec<CardDiagnostics>,
     conf: Config,
     lucid_client: Option<LucidClient>,
-    projects_client: ProjectsClient,
+    card_sources: Vec<Box<dyn CardSource>>,
+    cache: CacheMode,
 }
 
 impl App {
-    pub fn new(conf: Config, output_dir: &str) -> Result<Self, GeneratorError> {
+    pub fn new(
+        conf: Config,
+        output_dir: &str,
+        cache: CacheMode,
+        filter: Option<String>,
+    ) -> Result<Self, GeneratorError> {
         fs::create_dir_all(format!("{}/{}", output_dir, "images"))
             .map_err(|_| GeneratorError::InvalidOutputDirectory)?;
         let output_file = PathBuf::from(format!("{}/pld.md", output_dir));
@@ -72,24 +130,38 @@ impl App {
             f.set_len(0).unwrap();
         }
 
+        let card_template = match conf.doc.as_ref().and_then(|d| d.card_template.as_ref()) {
+            Some(path) => {
+                Some(fs::read_to_string(path).map_err(|_| GeneratorError::TemplateError)?)
+            }
+            None => None,
+        };
+
+        let filter = card_filter(&conf, filter)?;
+
         Ok(App {
             output_dir: output_dir.to_string(),
             output_file: File::create(output_file)
                 .map_err(|_| GeneratorError::InvalidOutputDirectory)?,
             output_buffer: fs::read_to_string("./template.md")
                 .map_err(|_| GeneratorError::TemplateError)?,
-            lucid_client: if let Some(lucid_conf) = conf.lucid.as_ref() {
-                Some(LucidClient::new(
+            card_template,
+            filter,
+            card_schema: conf.schema.clone().unwrap_or_default(),
+            diagnostics: Vec::new(),
+            lucid_client: conf.lucid.as_ref().map(|lucid_conf| {
+                LucidClient::new(
                     &lucid_conf.access_token,
                     &lucid_conf.refresh_token,
                     &lucid_conf.client_id,
                     &lucid_conf.client_secret,
-                ))
-            } else {
-                None
-            },
-            projects_client: ProjectsClient::new(&conf.github.api_key, conf.github.project_number),
+                    cache.clone(),
+                )
+            }),
+            card_sources: source::from_config(&conf, cache.clone())
+                .map_err(GeneratorError::Source)?,
             conf,
+            cache,
         })
     }
 
@@ -99,14 +171,17 @@ impl App {
         let lucid_conf = self.conf.lucid.as_mut().unwrap();
         let lucid_client = self.lucid_client.as_mut().unwrap();
 
-        if !(lucid_client
+        let valid = lucid_client
             .check_access_token(&lucid_conf.access_token)
-            .await)
-        {
-            let (new_access, new_refresh) = lucid_client
-                .refresh_token()
-                .await
-                .map_err(|_| GeneratorError::LucidInvalidRefreshToken)?;
+            .await
+            .map_err(lucid_error)?;
+
+        if !valid {
+            let (new_access, new_refresh) =
+                lucid_client.refresh_token().await.map_err(|e| match e {
+                    LucidError::ExpiredToken => GeneratorError::LucidInvalidRefreshToken,
+                    e => lucid_error(e),
+                })?;
 
             lucid_conf.access_token = new_access;
             lucid_conf.refresh_token = new_refresh;
@@ -116,7 +191,7 @@ impl App {
     }
 
     /// Downloads all images to the output directory and writes the diagram of the deliverables
-    async fn write_images_md(&mut self) {
+    async fn write_images_md(&mut self) -> Result<(), GeneratorError> {
         // Any lucid related functions should not be called if lucid conf or lucid client is None
         let lucid_conf = self.conf.lucid.as_mut().unwrap();
         let lucid_client = self.lucid_client.as_mut().unwrap();
@@ -125,23 +200,23 @@ impl App {
         let mut images_buf = Vec::new();
 
         if self.output_buffer.find(LUCID_TAG).is_none() {
-            return;
+            return Ok(());
         }
 
         let n_pages = lucid_client
             .get_page_count(&lucid_conf.document_id)
             .await
-            .expect("Error querying document page number lucid chart");
+            .map_err(lucid_error)?;
 
         for page in 1..=n_pages {
             let mut dest = PathBuf::from(&self.output_dir);
-            let image_path = format!("images/{}.png", page.to_string());
+            let image_path = format!("images/{}.png", page);
             dest.push(&image_path);
 
             lucid_client
                 .export_image(dest.to_str().unwrap(), &lucid_conf.document_id, page)
                 .await
-                .expect("Error downloading image");
+                .map_err(lucid_error)?;
             crop_image(&dest);
 
             image_paths.push(dest);
@@ -149,9 +224,7 @@ impl App {
 
         writeln!(images_buf, r#"<p align="center">"#).unwrap();
 
-        let (width, height) = if self.conf.doc.is_some() {
-            let doc_settings = self.conf.doc.as_ref().unwrap();
-
+        let (width, height) = if let Some(doc_settings) = self.conf.doc.as_ref() {
             let height = match &doc_settings.image_height {
                 Some(h) => format!("height = {}", h),
                 None => "".to_string(),
@@ -184,26 +257,64 @@ impl App {
         self.output_buffer = self
             .output_buffer
             .replace(LUCID_TAG, &String::from_utf8(images_buf).unwrap());
+
+        Ok(())
     }
 
-    async fn write_cards(&mut self) {
-        let cards: Vec<PldCard> = self
-            .projects_client
-            .get_cards()
+    /// Fetches and merges the cards of every source
+    ///
+    /// Display names of the `[people]` table replace the ones given by the sources.
+    async fn fetch_cards(&self) -> Result<MergedCards, GeneratorError> {
+        let mut merged = source::merge_cards(&self.card_sources)
             .await
+            .map_err(GeneratorError::Source)?;
+
+        if let Some(people) = &self.conf.people {
+            apply_people(&mut merged.cards, people);
+        }
+
+        Ok(merged)
+    }
+
+    /// True for the cards written in the PLD
+    fn is_kept(&self, card: &ProjectCard) -> bool {
+        card.working_days != 0.0 && self.filter.as_ref().is_none_or(|f| f.matches(card))
+    }
+
+    async fn write_cards(&mut self) -> Result<(), GeneratorError> {
+        let merged = self.fetch_cards().await?;
+
+        for (title, card_origins) in merged.duplicates(|card| self.is_kept(card)) {
+            println!(
+                "{} Card \"{}\" is present in several projects : {}",
+                "WARNING:".yellow(),
+                title.blue(),
+                card_origins.join(", ")
+            );
+        }
+
+        let mut diagnostics = Vec::new();
+        let mut cards: Vec<PldCard> = merged
+            .cards
             .iter()
+            .filter(|card| self.is_kept(card))
             .filter_map(|card| {
-                if card.working_days == 0.0 {
-                    return None;
+                for field in card.missing_fields() {
+                    println!(
+                        "{} Card \"{}\" has no {}",
+                        "WARNING:".yellow(),
+                        card.name.blue(),
+                        field.red()
+                    );
                 }
 
-                match PldCard::new(card) {
-                    Err(_) => {
-                        println!(
-                            "{}Skipping card \"{}\" due to parsing failure.",
-                            "WARNING: ".yellow(),
-                            card.name.yellow()
-                        );
+                match PldCard::new(card, &self.card_schema) {
+                    Err(errors) => {
+                        diagnostics.push(CardDiagnostics {
+                            name: card.name.clone(),
+                            url: card.url.clone(),
+                            errors,
+                        });
 
                         None
                     }
@@ -211,25 +322,102 @@ impl App {
                 }
             })
             .collect();
-        let sorted_cards = sort_by_section(cards);
+        self.diagnostics = diagnostics;
+
+        let graph = DependencyGraph::new(&cards);
+        report_dependency_issues(&graph, &cards);
+        let critical_path = critical_path_markdown(&graph, &cards);
+
+        let depends_on: Vec<Vec<(String, String)>> = (0..cards.len())
+            .map(|card| {
+                graph
+                    .dependencies(card)
+                    .iter()
+                    .map(|&dependency| {
+                        let dependency = &cards[dependency];
+                        (dependency.name.trim().to_string(), dependency.anchor())
+                    })
+                    .collect()
+            })
+            .collect();
+        for (card, depends_on) in cards.iter_mut().zip(depends_on) {
+            card.depends_on = depends_on;
+        }
+
+        let document_completion: Completion = cards.iter().map(PldCard::completion).sum();
+        let mut sorted_cards = sort_by_section(cards);
+        let numbering = self.numbering(&sorted_cards)?;
+        let order_of = |number: Option<String>| number.as_deref().map(number_order);
+
+        for card in sorted_cards
+            .values_mut()
+            .flat_map(|s| s.values_mut())
+            .flatten()
+        {
+            card.number = numbering.as_ref().and_then(|n| n.card(card));
+        }
+
+        // Numbered cards are written in the order of their numbers, others by title
+        let mut sections: Vec<_> = sorted_cards.into_iter().collect();
+        sections.sort_by_key(|(section, _)| {
+            order_of(numbering.as_ref().and_then(|n| n.section(section)))
+        });
 
         let mut cards_buf = Vec::new();
 
-        for (section_name, sub_section_map) in sorted_cards {
+        for (section_name, sub_section_map) in sections {
+            let section_completion: Completion = sub_section_map
+                .values()
+                .flatten()
+                .map(PldCard::completion)
+                .sum();
+
+            let completion_line = if section_completion.total > 0 {
+                format!("  <p>DOD completion : {}</p>\n", section_completion)
+            } else {
+                String::new()
+            };
+
+            let section_number = numbering.as_ref().and_then(|n| n.section(&section_name));
             write!(
                 cards_buf,
-                "<center>\n  <h2>{}</h2>\n</center>\n\n",
-                section_name
+                "<center>\n  <h2>{}</h2>\n{}</center>\n\n",
+                numbered(section_number, &section_name),
+                completion_line
             )
             .unwrap();
 
-            for (subsection_name, sub_section_cards) in sub_section_map.iter() {
+            let mut sub_sections: Vec<_> = sub_section_map.into_iter().collect();
+            sub_sections.sort_by_key(|(sub_section, _)| {
+                order_of(
+                    numbering
+                        .as_ref()
+                        .and_then(|n| n.sub_section(&section_name, sub_section)),
+                )
+            });
+
+            for (subsection_name, mut sub_section_cards) in sub_sections {
+                sub_section_cards.sort_by_key(|card| order_of(card.number.clone()));
+                let sub_section_completion: Completion =
+                    sub_section_cards.iter().map(PldCard::completion).sum();
+
+                let sub_section_number = numbering
+                    .as_ref()
+                    .and_then(|n| n.sub_section(&section_name, &subsection_name));
                 write!(
                     cards_buf,
                     "### {}\n\n<hr style=\"height: 3px\">\n\n",
-                    subsection_name
+                    numbered(sub_section_number, &subsection_name)
                 )
                 .unwrap();
+                if sub_section_completion.total > 0 {
+                    write!(
+                        cards_buf,
+                        "**DOD completion :** {}\n\n",
+                        sub_section_completion
+                    )
+                    .unwrap();
+                }
 
                 for (i, card) in sub_section_cards.iter().enumerate() {
                     let separator = if i == sub_section_cards.len() - 1 {
@@ -238,14 +426,26 @@ impl App {
                         "<hr style=\"height: 1px\">\n\n"
                     };
 
-                    write!(cards_buf, "#### {}\n\n{}", card, separator).unwrap();
+                    write!(cards_buf, "<a id=\"{}\"></a>\n\n", card.anchor()).unwrap();
+                    match &self.card_template {
+                        Some(template) => {
+                            write!(cards_buf, "{}\n\n{}", card.render(template), separator)
+                        }
+                        None => write!(cards_buf, "#### {}\n\n{}", card, separator),
+                    }
+                    .unwrap();
                 }
             }
         }
 
+        // Cards are inserted last so that tags written in card bodies are kept as is
         self.output_buffer = self
             .output_buffer
+            .replace(DOD_COMPLETION_TAG, &document_completion.to_string())
+            .replace(CRITICAL_PATH_TAG, &critical_path)
             .replace(CARDS_TAG, &String::from_utf8(cards_buf).unwrap());
+
+        Ok(())
     }
 
     fn write_table_of_contents(&mut self) {
@@ -282,7 +482,7 @@ impl App {
                 let toc_entry = format!(
                     "{indentation}- [{title}](#{link})",
                     indentation = "  ".repeat(header_level - 2),
-                    title = match_str.to_string()
+                    title = match_str
                 );
 
                 Some(toc_entry)
@@ -292,34 +492,177 @@ impl App {
         self.output_buffer = self.output_buffer.replace(TOC_TAG, &toc_items.join("\n"));
     }
 
+    /// Numbers the sorted cards according to the numbering setting, numbers kept across runs are
+    /// read from and saved to the output directory
+    fn numbering(
+        &self,
+        sorted_cards: &BTreeMap<String, BTreeMap<String, Vec<PldCard>>>,
+    ) -> Result<Option<Numbering>, GeneratorError> {
+        let path = PathBuf::from(&self.output_dir).join(NUMBERING_FILE);
+
+        let mut numbering = match self.conf.doc.as_ref().and_then(|d| d.numbering) {
+            None => return Ok(None),
+            Some(NumberingMode::Position) => Numbering::default(),
+            Some(NumberingMode::Stable) => {
+                Numbering::load(&path).map_err(|_| GeneratorError::NumberingFailed)?
+            }
+        };
+
+        numbering.assign(sorted_cards);
+
+        if let Some(NumberingMode::Stable) = self.conf.doc.as_ref().and_then(|d| d.numbering) {
+            numbering
+                .save(&path)
+                .map_err(|_| GeneratorError::NumberingFailed)?;
+        }
+
+        Ok(Some(numbering))
+    }
+
+    /// Date of generation, taken from the recording when replaying so that the PLD is
+    /// reproduced exactly
+    fn generation_date(&self) -> Result<String, GeneratorError> {
+        if let Some(date) = self
+            .cache
+            .replay(DATE_RECORDING)
+            .map_err(GeneratorError::Cache)?
+        {
+            return String::from_utf8(date).map_err(|e| {
+                GeneratorError::Cache(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} : {}", DATE_RECORDING, e),
+                ))
+            });
+        }
+
+        let date = format!("{}", chrono::offset::Local::now().format("%d/%m/%Y"));
+        self.cache
+            .record(DATE_RECORDING, date.as_bytes())
+            .map_err(GeneratorError::Cache)?;
+
+        Ok(date)
+    }
+
     /// Run generator
     pub async fn run(&mut self) -> Result<(), GeneratorError> {
+        // Checked first so that a wrong field mapping is reported before any lucid request
+        for source in &self.card_sources {
+            source.validate().await.map_err(GeneratorError::Source)?;
+        }
+
         if self.lucid_client.is_some() {
-            self.ensure_lucid_token_validity().await?;
+            // Tokens are not needed when replaying recorded responses
+            if !self.cache.is_replay() {
+                self.ensure_lucid_token_validity().await?;
+            }
 
-            self.write_images_md().await;
+            self.write_images_md().await?;
         } else {
             // Remove the lucid tag
             self.output_buffer = self.output_buffer.replace(LUCID_TAG, "");
         }
 
-        self.write_cards().await;
+        self.write_cards().await?;
 
         self.write_table_of_contents();
 
-        self.output_buffer = self.output_buffer.replace(
-            "{{date_now}}",
-            &format!("{}", chrono::offset::Local::now().format("%d/%m/%Y")),
-        );
+        let date = self.generation_date()?;
+        self.output_buffer = self.output_buffer.replace(DATE_TAG, &date);
 
         self.output_file
             .write(self.output_buffer.as_bytes())
             .map_err(|_| GeneratorError::WriteFailed)?;
 
+        for source in &self.card_sources {
+            source.report();
+        }
+
+        print_report(&self.diagnostics, &self.card_schema);
+
         Ok(())
     }
 }
 
+/// Recorded responses missing when replaying are reported as such, naming the missing file
+fn lucid_error(e: LucidError) -> GeneratorError {
+    match e {
+        LucidError::CacheError(e) => GeneratorError::Cache(e),
+        e => GeneratorError::Lucid(e),
+    }
+}
+
+/// Title preceded by its number, if it has one
+fn numbered(number: Option<String>, title: &str) -> String {
+    match number {
+        Some(number) => format!("{} {}", number, title),
+        None => title.to_string(),
+    }
+}
+
+/// Sets the display name of every assignee listed in `people`, indexed by login
+fn apply_people(cards: &mut [ProjectCard], people: &BTreeMap<String, String>) {
+    for card in cards {
+        for login in &card.assignees {
+            if let Some(name) = people.get(login) {
+                card.assignee_names.insert(login.clone(), name.clone());
+            }
+        }
+    }
+}
+
+/// Warns about dependencies matching no card and cycles of dependencies
+fn report_dependency_issues(graph: &DependencyGraph, cards: &[PldCard]) {
+    for (card, reference) in &graph.dangling {
+        println!(
+            "{} Card \"{}\" depends on \"{}\", which matches no card of the PLD",
+            "WARNING:".yellow(),
+            cards[*card].name.trim().blue(),
+            reference.red()
+        );
+    }
+
+    for cycle in &graph.cycles {
+        println!(
+            "{} Cards depend on each other : {}",
+            "WARNING:".yellow(),
+            cycle
+                .iter()
+                .map(|&card| format!("\"{}\"", cards[card].name.trim().blue()))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        );
+    }
+}
+
+/// Numbered list of the cards of the critical path, linking to each card
+fn critical_path_markdown(graph: &DependencyGraph, cards: &[PldCard]) -> String {
+    if graph.is_empty() {
+        return "No card depends on another one".to_string();
+    }
+
+    let path = graph.critical_path(cards);
+    let items: Vec<String> = path
+        .iter()
+        .enumerate()
+        .map(|(i, &card)| {
+            format!(
+                "{}. [{}](#{}) : {} working day(s)",
+                i + 1,
+                cards[card].name.trim(),
+                cards[card].anchor(),
+                cards[card].working_days
+            )
+        })
+        .collect();
+    let total: f32 = path.iter().map(|&card| cards[card].working_days).sum();
+
+    format!(
+        "{}\n\n**Total :** {} working day(s)",
+        items.join("\n"),
+        total
+    )
+}
+
 impl Drop for App {
     fn drop(&mut self) {
         let mut file = File::create(&self.conf.path).unwrap();
@@ -327,3 +670,32 @@ impl Drop for App {
             .unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::card::fixtures::{self, BODY};
+
+    #[test]
+    fn people_names_replace_the_source_names() {
+        let mut cards = [ProjectCard {
+            assignees: ["octocat", "ghost", "jdoe"].map(String::from).to_vec(),
+            assignee_names: BTreeMap::from([
+                ("octocat".to_string(), "The Octocat".to_string()),
+                ("ghost".to_string(), "Ghost".to_string()),
+            ]),
+            ..fixtures::card("1.1 Login", BODY)
+        }];
+        let people = BTreeMap::from([
+            ("octocat".to_string(), "Mona Lisa".to_string()),
+            ("nobody".to_string(), "Nobody".to_string()),
+        ]);
+
+        apply_people(&mut cards, &people);
+        let card = PldCard::new(&cards[0], &CardSchema::default()).unwrap();
+
+        // Logins with no display name are shown as is
+        assert_eq!(card.assignees, ["Mona Lisa", "Ghost", "jdoe"]);
+        assert!(!cards[0].assignee_names.contains_key("nobody"));
+    }
+}
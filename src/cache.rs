@@ -0,0 +1,39 @@
+use std::{fs, io, path::PathBuf};
+
+/// Controls whether network responses are fetched live, recorded to or replayed from a directory
+#[derive(Debug, Clone, Default)]
+pub enum CacheMode {
+    #[default]
+    Live,
+    /// Fetch responses live and save each of them to the directory
+    Record(PathBuf),
+    /// Read every response from the directory without any network access
+    Replay(PathBuf),
+}
+
+impl CacheMode {
+    pub fn is_replay(&self) -> bool {
+        matches!(self, CacheMode::Replay(_))
+    }
+
+    /// Reads the recorded response `name`, returns `None` unless replaying
+    pub fn replay(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        match self {
+            CacheMode::Replay(dir) => fs::read(dir.join(name))
+                .map(Some)
+                .map_err(|e| io::Error::new(e.kind(), format!("{} : {}", name, e))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Saves the response `name`, does nothing unless recording
+    pub fn record(&self, name: &str, content: &[u8]) -> io::Result<()> {
+        match self {
+            CacheMode::Record(dir) => {
+                fs::create_dir_all(dir)?;
+                fs::write(dir.join(name), content)
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,9 +1,227 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+/// Kind of account owning a github project
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnerKind {
+    #[default]
+    Organization,
+    User,
+}
+
+/// Names of the project fields holding each piece of card information
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FieldsConfig {
+    pub working_days: String,
+    pub section: String,
+    pub sub_section: String,
+    pub status: String,
+}
+
+impl Default for FieldsConfig {
+    fn default() -> Self {
+        FieldsConfig {
+            working_days: "Working Days".to_string(),
+            section: "Section".to_string(),
+            sub_section: "Sub-Section".to_string(),
+            status: "Status".to_string(),
+        }
+    }
+}
+
+/// One of the github projects the cards are merged from
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GithubProject {
+    pub number: u32,
+    /// Defaults to the owner of the `[github]` table
+    pub owner: Option<String>,
+    pub owner_kind: Option<OwnerKind>,
+    /// Prepended to the section of every card of the project
+    pub section_prefix: Option<String>,
+    /// Replaces the section of every card of the project
+    pub section: Option<String>,
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GithubConfig {
     pub api_key: String,
-    pub project_number: u8,
+    /// Login of the organization or user owning the projects
+    pub owner: String,
+    #[serde(default)]
+    pub owner_kind: OwnerKind,
+    /// Shorthand for a single entry in `projects`
+    pub project_number: Option<u32>,
+    #[serde(default)]
+    pub projects: Vec<GithubProject>,
+    #[serde(default)]
+    pub fields: FieldsConfig,
+}
+
+impl GithubConfig {
+    /// Every configured project, `project_number` included
+    pub fn all_projects(&self) -> Vec<GithubProject> {
+        let shorthand = self.project_number.map(|number| GithubProject {
+            number,
+            owner: None,
+            owner_kind: None,
+            section_prefix: None,
+            section: None,
+        });
+
+        shorthand
+            .into_iter()
+            .chain(self.projects.iter().cloned())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GitlabConfig {
+    /// Base url of the gitlab instance, ie: https://gitlab.com
+    pub url: String,
+    /// Personal or project access token with the `read_api` scope
+    pub token: String,
+    /// Project id or full path such as `group/project`
+    pub project: String,
+    /// Only keep issues of this milestone
+    pub milestone: Option<String>,
+    /// Only keep issues matching the milestone and labels of this board
+    pub board_id: Option<u64>,
+    /// Only keep issues having all of these labels
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Scope of the labels holding the section, ie: `Section::Backend`
+    #[serde(default = "default_section_label")]
+    pub section_label: String,
+    #[serde(default = "default_sub_section_label")]
+    pub sub_section_label: String,
+    #[serde(default = "default_status_label")]
+    pub status_label: String,
+    /// Used to convert time estimates to working days
+    #[serde(default = "default_hours_per_day")]
+    pub hours_per_day: f32,
+}
+
+fn default_section_label() -> String {
+    "Section".to_string()
+}
+
+fn default_sub_section_label() -> String {
+    "Sub-Section".to_string()
+}
+
+fn default_status_label() -> String {
+    "Status".to_string()
+}
+
+fn default_hours_per_day() -> f32 {
+    8.0
+}
+
+/// Jira information used as the card section
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JiraSection {
+    /// Summary of the parent epic
+    #[default]
+    Epic,
+    /// Name of the first component
+    Component,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JiraConfig {
+    /// Base url of the jira instance, ie: https://team.atlassian.net
+    pub url: String,
+    /// Account email, only for jira cloud
+    pub email: Option<String>,
+    /// API token on jira cloud, personal access token on data center
+    pub token: String,
+    pub jql: String,
+    #[serde(default)]
+    pub section: JiraSection,
+    /// Id of the custom field holding the sub-section, ie: `customfield_10050`
+    pub sub_section_field: Option<String>,
+    /// Id of the custom field holding the story points, ie: `customfield_10016`
+    pub story_points_field: Option<String>,
+    /// Used to convert original estimates to working days
+    #[serde(default = "default_hours_per_day")]
+    pub hours_per_day: f32,
+}
+
+/// Header names of the CSV columns holding each piece of card information
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CsvColumns {
+    pub name: String,
+    /// Column holding an identifier of the card kept across runs, the name is used when not set
+    pub id: Option<String>,
+    /// Column holding the whole card body, used instead of the four columns below when set
+    pub body: Option<String>,
+    pub user: String,
+    pub action: String,
+    pub description: String,
+    pub dod: String,
+    pub section: String,
+    pub sub_section: String,
+    pub status: String,
+    pub working_days: String,
+    /// Assignees separated by commas or semicolons
+    pub assignees: String,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        CsvColumns {
+            name: "Name".to_string(),
+            id: None,
+            body: None,
+            user: "As the".to_string(),
+            action: "I want to".to_string(),
+            description: "Description".to_string(),
+            dod: "DOD".to_string(),
+            section: "Section".to_string(),
+            sub_section: "Sub-Section".to_string(),
+            status: "Status".to_string(),
+            working_days: "Working Days".to_string(),
+            assignees: "Assignees".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CsvConfig {
+    pub path: String,
+    /// Single byte separator of the cells, hence ASCII only
+    #[serde(default = "default_delimiter", deserialize_with = "ascii_delimiter")]
+    pub delimiter: char,
+    #[serde(default)]
+    pub columns: CsvColumns,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn ascii_delimiter<'de, D: Deserializer<'de>>(deserializer: D) -> Result<char, D::Error> {
+    let delimiter = char::deserialize(deserializer)?;
+
+    if delimiter.is_ascii() {
+        Ok(delimiter)
+    } else {
+        Err(de::Error::custom(format!(
+            "the csv delimiter '{}' is not an ASCII character",
+            delimiter
+        )))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MarkdownConfig {
+    /// Directory containing one markdown file per card
+    pub path: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -19,14 +237,129 @@ pub struct LucidConfig {
 pub struct DocumentSettings {
     pub image_width: Option<String>,
     pub image_height: Option<String>,
+    /// Path to a template used to render each card instead of the default layout
+    pub card_template: Option<String>,
+    /// Expression selecting the cards written to the PLD, ie: `sprint == "Sprint 4"`
+    pub filter: Option<String>,
+    /// Numbers the sections, sub-sections and cards, ie: `2.3.1`
+    pub numbering: Option<NumberingMode>,
+}
+
+/// How the sections, sub-sections and cards are numbered
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberingMode {
+    /// Numbers follow the order of the PLD and may change from one run to the next
+    Position,
+    /// Numbers are saved in the output directory and kept across runs
+    Stable,
+}
+
+/// Alternative headings and requirement of one of the card sections
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SectionSchema {
+    /// Headings accepted in addition to the default one, ie: "Definition of Done"
+    pub aliases: Vec<String>,
+    /// Cards missing an optional section are still written to the PLD
+    pub optional: bool,
+}
+
+/// Layout expected in card bodies
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CardSchema {
+    pub user_wish: SectionSchema,
+    pub description: SectionSchema,
+    pub dod: SectionSchema,
+    /// Bold markers introducing the user of the wish
+    pub user_markers: Vec<String>,
+    /// Bold markers introducing the action of the wish
+    pub action_markers: Vec<String>,
+    /// Bold markers introducing the optional benefit of the wish
+    pub benefit_markers: Vec<String>,
+    /// Phrasings starting a line that lists the cards this one depends on
+    pub dependency_markers: Vec<String>,
+    /// Other sections captured from the cards and rendered after the definition of done
+    pub extra_sections: Vec<String>,
+}
+
+impl Default for CardSchema {
+    fn default() -> Self {
+        CardSchema {
+            user_wish: SectionSchema::default(),
+            description: SectionSchema::default(),
+            dod: SectionSchema::default(),
+            user_markers: ["As the:", "As a:", "As an:", "En tant que :"]
+                .map(String::from)
+                .to_vec(),
+            action_markers: ["I want to:", "Je veux :"].map(String::from).to_vec(),
+            benefit_markers: ["So that:", "Afin que :", "Pour que :"]
+                .map(String::from)
+                .to_vec(),
+            dependency_markers: ["Depends on", "Blocked by", "Dépend de", "Bloqué par"]
+                .map(String::from)
+                .to_vec(),
+            extra_sections: Vec::new(),
+        }
+    }
+}
+
+/// Backend the cards are fetched from
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Csv,
+    #[default]
+    Github,
+    Gitlab,
+    Jira,
+    Markdown,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
-    pub github: GithubConfig,
+    #[serde(default)]
+    pub source: SourceKind,
+    pub github: Option<GithubConfig>,
+    pub csv: Option<CsvConfig>,
+    pub gitlab: Option<GitlabConfig>,
+    pub jira: Option<JiraConfig>,
+    pub markdown: Option<MarkdownConfig>,
     pub lucid: Option<LucidConfig>,
     #[serde(rename = "document-settings")]
     pub doc: Option<DocumentSettings>,
+    #[serde(rename = "card-schema")]
+    pub schema: Option<CardSchema>,
+    /// Display names of the assignees indexed by login, they take precedence over the names
+    /// given by the card source
+    pub people: Option<BTreeMap<String, String>>,
     #[serde(skip)]
     pub path: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_numbers_above_255() {
+        let conf: GithubConfig = toml::from_str(
+            "api_key = \"token\"\nowner = \"Autogrower\"\nproject_number = 300\n\n\
+             [[projects]]\nnumber = 1024",
+        )
+        .unwrap();
+
+        let numbers: Vec<u32> = conf.all_projects().iter().map(|p| p.number).collect();
+        assert_eq!(numbers, [300, 1024]);
+    }
+
+    #[test]
+    fn missing_owner_is_reported() {
+        let error = toml::from_str::<Config>("[github]\napi_key = \"token\"\nproject_number = 1")
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("missing field `owner`"), "{}", error);
+    }
+}
@@ -0,0 +1,137 @@
+use colored::Colorize;
+use similar::{ChangeTag, TextDiff};
+
+use crate::{
+    app::{card_filter, GeneratorError},
+    cache::CacheMode,
+    config::{CardSchema, Config},
+    parsing::PldCard,
+    source::{self, filter::Filter, CardSource},
+};
+
+/// Rewrites the card bodies in the layout of `card_format.md` and pushes them to their source
+pub struct Fixer {
+    card_sources: Vec<Box<dyn CardSource>>,
+    card_schema: CardSchema,
+    filter: Option<Filter>,
+}
+
+impl Fixer {
+    pub fn new(
+        conf: &Config,
+        cache: CacheMode,
+        filter: Option<String>,
+    ) -> Result<Fixer, GeneratorError> {
+        Ok(Fixer {
+            card_sources: source::from_config(conf, cache).map_err(GeneratorError::Source)?,
+            card_schema: conf.schema.clone().unwrap_or_default(),
+            filter: card_filter(conf, filter)?,
+        })
+    }
+
+    /// Prints the changes of every card whose body is not in the canonical layout, and updates
+    /// them in their source when `apply` is set
+    ///
+    /// Cards that cannot be parsed are left untouched, `lint` explains what is wrong with them.
+    /// Returns the number of cards which need changes but could not be updated.
+    pub async fn run(&self, apply: bool) -> Result<usize, GeneratorError> {
+        for source in &self.card_sources {
+            source.validate().await.map_err(GeneratorError::Source)?;
+        }
+
+        let mut changed = 0;
+        let mut unparsed = 0;
+        let mut failed = 0;
+        // Cards whose user wish section holds text which would be lost
+        let mut kept = Vec::new();
+
+        for source in &self.card_sources {
+            let cards = source.get_cards().await.map_err(GeneratorError::Source)?;
+
+            for card in cards
+                .iter()
+                .filter(|card| self.filter.as_ref().is_none_or(|f| f.matches(card)))
+            {
+                let Ok(parsed) = PldCard::new(card, &self.card_schema) else {
+                    unparsed += 1;
+                    continue;
+                };
+
+                let Some(fixed) = parsed.to_markdown(&self.card_schema) else {
+                    kept.push(card.name.trim().to_string());
+                    continue;
+                };
+                let current = card.content.replace("\r\n", "\n");
+                if current.trim_end() == fixed.trim_end() {
+                    continue;
+                }
+
+                changed += 1;
+                match &card.url {
+                    Some(url) => println!("\"{}\" ({})", card.name.trim().blue(), url),
+                    None => println!("\"{}\"", card.name.trim().blue()),
+                }
+                print_diff(current.trim_end(), fixed.trim_end());
+
+                if apply {
+                    match source.update_body(card, &fixed).await {
+                        Ok(()) => println!("{}", "Updated".green()),
+                        Err(e) => {
+                            failed += 1;
+                            println!("{} {}", "ERROR:".red(), e);
+                        }
+                    }
+                }
+                println!();
+            }
+        }
+
+        if unparsed > 0 {
+            println!(
+                "{} {} card(s) could not be parsed and were left untouched, run lint for details",
+                "WARNING:".yellow(),
+                unparsed
+            );
+        }
+
+        for name in kept {
+            println!(
+                "{} \"{}\" holds text outside of its wishes in the user wish section, it was left untouched",
+                "WARNING:".yellow(),
+                name.blue()
+            );
+        }
+
+        if changed == 0 {
+            println!("Every card is already formatted");
+        } else if apply {
+            println!("{} of {} card(s) updated", changed - failed, changed);
+        } else {
+            println!(
+                "{} card(s) would be updated, run again with --yes to push the changes",
+                changed
+            );
+        }
+
+        Ok(failed)
+    }
+}
+
+/// Prints a unified diff of the card body, removed lines in red and added lines in green
+fn print_diff(old: &str, new: &str) {
+    let diff = TextDiff::from_lines(old, new);
+
+    for hunk in diff.unified_diff().context_radius(2).iter_hunks() {
+        println!("{}", hunk.header().to_string().cyan());
+        for change in hunk.iter_changes() {
+            let line = change.to_string_lossy();
+            let line = line.trim_end_matches('\n');
+
+            match change.tag() {
+                ChangeTag::Delete => println!("{}", format!("-{}", line).red()),
+                ChangeTag::Insert => println!("{}", format!("+{}", line).green()),
+                ChangeTag::Equal => println!(" {}", line),
+            }
+        }
+    }
+}
@@ -1,21 +1,14 @@
-use colored::Colorize;
 use serde::{self, Deserialize};
 
-/// Structure representing one card on the project
+use crate::source::card::{FieldValue, IssueState, ItemType, ProjectCard};
+
+/// Item of a project, `None` when its content cannot be read
 #[derive(Debug)]
-pub struct ProjectCard {
-    pub name: String,
-    pub content: String,
-    pub section: String,
-    pub sub_section: String,
-    pub working_days: f32,
-    pub status: String,
-    pub assignees: Vec<String>,
-}
+pub struct ProjectItem(pub Option<ProjectCard>);
 
 // This deserializer implementation allows for deserializing a given card (aka Node)
 // while ditching the unnecessary nesting from the response
-impl<'de> Deserialize<'de> for ProjectCard {
+impl<'de> Deserialize<'de> for ProjectItem {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
@@ -23,6 +16,8 @@ impl<'de> Deserialize<'de> for ProjectCard {
         #[derive(Deserialize)]
         struct User {
             login: String,
+            /// Name set on the github profile, if any
+            name: Option<String>,
         }
 
         #[derive(Deserialize)]
@@ -30,11 +25,51 @@ impl<'de> Deserialize<'de> for ProjectCard {
             nodes: Vec<User>,
         }
 
+        #[derive(Deserialize)]
+        struct Label {
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Labels {
+            nodes: Vec<Label>,
+        }
+
+        #[derive(Deserialize)]
+        struct Repository {
+            #[serde(rename = "nameWithOwner")]
+            name_with_owner: String,
+        }
+
+        #[derive(Deserialize)]
+        struct LinkedIssue {
+            url: String,
+        }
+
+        #[derive(Deserialize)]
+        struct LinkedIssues {
+            nodes: Vec<LinkedIssue>,
+        }
+
         #[derive(Deserialize)]
         struct Content {
+            #[serde(rename = "__typename")]
+            typename: String,
+            id: String,
             title: String,
             body: String,
             assignees: Assignees,
+            /// Only set for issues and pull requests
+            number: Option<u64>,
+            url: Option<String>,
+            state: Option<String>,
+            repository: Option<Repository>,
+            labels: Option<Labels>,
+            /// Issues listed in the tasklists of an issue
+            #[serde(rename = "trackedIssues")]
+            tracked_issues: Option<LinkedIssues>,
+            #[serde(rename = "subIssues")]
+            sub_issues: Option<LinkedIssues>,
         }
 
         #[derive(Deserialize)]
@@ -57,77 +92,256 @@ impl<'de> Deserialize<'de> for ProjectCard {
             name: String,
         }
 
+        #[derive(Deserialize)]
+        struct FieldName {
+            name: String,
+        }
+
+        // Field values are tagged by their graphql type name, the ones we do not handle
+        // (labels, milestones, etc.) are simply ignored
+        #[derive(Deserialize)]
+        #[serde(tag = "__typename")]
+        enum RawFieldValue {
+            #[serde(rename = "ProjectV2ItemFieldTextValue")]
+            Text {
+                text: Option<String>,
+                field: FieldName,
+            },
+            #[serde(rename = "ProjectV2ItemFieldNumberValue")]
+            Number {
+                number: Option<f64>,
+                field: FieldName,
+            },
+            #[serde(rename = "ProjectV2ItemFieldDateValue")]
+            Date {
+                date: Option<String>,
+                field: FieldName,
+            },
+            #[serde(rename = "ProjectV2ItemFieldSingleSelectValue")]
+            SingleSelect {
+                name: Option<String>,
+                field: FieldName,
+            },
+            #[serde(rename = "ProjectV2ItemFieldIterationValue")]
+            Iteration { title: String, field: FieldName },
+            #[serde(other)]
+            Other,
+        }
+
+        #[derive(Deserialize)]
+        struct FieldValues {
+            nodes: Vec<RawFieldValue>,
+        }
+
         #[derive(Deserialize)]
         struct Node {
-            content: Content,
-            working_days: WorkingDays,
+            content: Option<Content>,
+            working_days: Option<WorkingDays>,
             section: Option<Section>,
             sub_section: Option<SubSection>,
             _assignees: Option<Assignees>,
             status: Option<Status>,
+            #[serde(rename = "fieldValues")]
+            field_values: FieldValues,
+            #[serde(rename = "isArchived")]
+            is_archived: bool,
         }
 
         let helper = Node::deserialize(deserializer)?;
+        // Content the token cannot read, such as an issue of a private repository, is null
+        let Some(content) = helper.content else {
+            return Ok(ProjectItem(None));
+        };
 
-        Ok(ProjectCard {
-            name: helper.content.title.clone(),
-            content: helper.content.body,
-            section: if let Some(section) = helper.section {
-                section.name
-            } else {
-                println!(
-                    "{} Card \"{}\" has no {}",
-                    "WARNING:".yellow(),
-                    &helper.content.title.blue(),
-                    "Section".red()
-                );
-                "".to_string()
-            },
-            working_days: helper.working_days.number,
-            sub_section: if let Some(subsection) = helper.sub_section {
-                subsection.name
-            } else {
-                println!(
-                    "{} Card \"{}\" has no {}",
-                    "WARNING:".yellow(),
-                    helper.content.title.blue(),
-                    "Sub-section".red()
-                );
-                "".to_string()
-            },
-            status: if let Some(status) = helper.status {
-                status.name
-            } else {
-                println!(
-                    "{} Card \"{}\" has no {}",
-                    "WARNING:".yellow(),
-                    helper.content.title.blue(),
-                    "Status".red()
-                );
-                "".to_string()
-            },
-            assignees: helper
-                .content
+        Ok(ProjectItem(Some(ProjectCard {
+            name: content.title.clone(),
+            content: content.body,
+            section: helper
+                .section
+                .map(|section| section.name)
+                .unwrap_or_default(),
+            // Cards without working days are left out of the PLD
+            working_days: helper.working_days.map_or(0.0, |w| w.number),
+            sub_section: helper
+                .sub_section
+                .map(|subsection| subsection.name)
+                .unwrap_or_default(),
+            status: helper.status.map(|status| status.name).unwrap_or_default(),
+            assignees: content
                 .assignees
                 .nodes
                 .iter()
                 .map(|e| e.login.clone())
                 .collect(),
-        })
+            assignee_names: content
+                .assignees
+                .nodes
+                .iter()
+                .filter_map(|e| {
+                    let name = e.name.clone().filter(|name| !name.is_empty())?;
+                    Some((e.login.clone(), name))
+                })
+                .collect(),
+            fields: helper
+                .field_values
+                .nodes
+                .into_iter()
+                .filter_map(|value| match value {
+                    RawFieldValue::Text { text, field } => {
+                        Some((field.name, FieldValue::Text(text?)))
+                    }
+                    RawFieldValue::Number { number, field } => {
+                        Some((field.name, FieldValue::Number(number?)))
+                    }
+                    RawFieldValue::Date { date, field } => {
+                        Some((field.name, FieldValue::Date(date?)))
+                    }
+                    RawFieldValue::SingleSelect { name, field } => {
+                        Some((field.name, FieldValue::SingleSelect(name?)))
+                    }
+                    RawFieldValue::Iteration { title, field } => {
+                        Some((field.name, FieldValue::Iteration(title)))
+                    }
+                    RawFieldValue::Other => None,
+                })
+                .collect(),
+            labels: content
+                .labels
+                .map(|labels| labels.nodes.into_iter().map(|l| l.name).collect())
+                .unwrap_or_default(),
+            state: content.state.map(|state| match state.as_str() {
+                "OPEN" => IssueState::Open,
+                // Merged pull requests are considered closed
+                _ => IssueState::Closed,
+            }),
+            item_type: match content.typename.as_str() {
+                "Issue" => ItemType::Issue,
+                "PullRequest" => ItemType::PullRequest,
+                _ => ItemType::Draft,
+            },
+            archived: helper.is_archived,
+            // Issue numbers are only unique within a repository
+            references: content
+                .repository
+                .as_ref()
+                .zip(content.number)
+                .map(|(repository, number)| format!("{}#{}", repository.name_with_owner, number))
+                .into_iter()
+                .chain(content.url.clone())
+                .collect(),
+            repository: content
+                .repository
+                .map(|repository| repository.name_with_owner),
+            dependencies: Vec::new(),
+            // Tracking issues and parent issues depend on their tracked issues and sub-issues
+            linked_issues: content
+                .tracked_issues
+                .into_iter()
+                .chain(content.sub_issues)
+                .flat_map(|issues| issues.nodes)
+                .map(|issue| issue.url)
+                .collect(),
+            url: content.url,
+            id: Some(content.id),
+        })))
     }
 }
 
 #[derive(Deserialize, Debug)]
 pub struct PagingInfo {
+    #[serde(rename = "hasNextPage")]
+    pub has_next_page: bool,
     #[serde(rename = "endCursor")]
-    pub end_cursor: String,
+    pub end_cursor: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ProjectItems {
     #[serde(rename = "totalCount")]
     pub total_count: usize,
-    pub nodes: Vec<ProjectCard>,
+    pub nodes: Vec<ProjectItem>,
     #[serde(rename = "pageInfo")]
     pub paging_info: PagingInfo,
 }
+
+/// Rate limit budget returned along with every cards query
+#[derive(Deserialize, Debug, Clone)]
+pub struct RateLimit {
+    pub cost: u32,
+    pub remaining: u32,
+    #[serde(rename = "resetAt")]
+    pub reset_at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn node(field_values: serde_json::Value) -> serde_json::Value {
+        json!({
+            "isArchived": false,
+            "content": {
+                "__typename": "DraftIssue",
+                "id": "DI_1",
+                "title": "1.1 Login",
+                "body": "",
+                "assignees": { "nodes": [] }
+            },
+            "working_days": { "number": 2 },
+            "section": null,
+            "sub_section": null,
+            "status": { "name": "Todo" },
+            "fieldValues": { "nodes": field_values }
+        })
+    }
+
+    fn card(field_values: serde_json::Value) -> ProjectCard {
+        let item: ProjectItem = serde_json::from_value(node(field_values)).unwrap();
+
+        item.0.unwrap()
+    }
+
+    #[test]
+    fn unreadable_content_gives_no_card() {
+        let mut node = node(json!([]));
+        node["content"] = serde_json::Value::Null;
+
+        let item: ProjectItem = serde_json::from_value(node).unwrap();
+        assert!(item.0.is_none());
+    }
+
+    #[test]
+    fn field_values_are_read_by_type() {
+        let card = card(json!([
+            { "__typename": "ProjectV2ItemFieldTextValue", "text": "High", "field": { "name": "Priority" } },
+            { "__typename": "ProjectV2ItemFieldNumberValue", "number": 3.5, "field": { "name": "Points" } },
+            { "__typename": "ProjectV2ItemFieldDateValue", "date": "2024-05-01", "field": { "name": "Due" } },
+            { "__typename": "ProjectV2ItemFieldSingleSelectValue", "name": "Backend", "field": { "name": "Team" } },
+            { "__typename": "ProjectV2ItemFieldIterationValue", "title": "Sprint 4", "field": { "name": "Sprint" } }
+        ]));
+
+        assert!(matches!(&card.fields["Priority"], FieldValue::Text(text) if text == "High"));
+        assert!(matches!(card.fields["Points"], FieldValue::Number(number) if number == 3.5));
+        assert!(matches!(&card.fields["Due"], FieldValue::Date(date) if date == "2024-05-01"));
+        assert!(
+            matches!(&card.fields["Team"], FieldValue::SingleSelect(name) if name == "Backend")
+        );
+        assert!(
+            matches!(&card.fields["Sprint"], FieldValue::Iteration(title) if title == "Sprint 4")
+        );
+        assert_eq!(card.working_days, 2.0);
+        assert_eq!(card.status, "Todo");
+    }
+
+    #[test]
+    fn unsupported_and_empty_values_are_ignored() {
+        let card = card(json!([
+            { "__typename": "ProjectV2ItemFieldLabelValue" },
+            { "__typename": "ProjectV2ItemFieldTextValue", "text": null, "field": { "name": "Notes" } },
+            { "__typename": "ProjectV2ItemFieldUserValue", "users": { "nodes": [] } }
+        ]));
+
+        assert!(card.fields.is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt::{self, Display};
+
+use serde::Deserialize;
+
+/// Data type of a project field as reported by the github graphql API
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FieldType {
+    Number,
+    SingleSelect,
+    Text,
+    Date,
+    Iteration,
+    #[serde(other)]
+    Other,
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Number => write!(f, "number"),
+            FieldType::SingleSelect => write!(f, "single select"),
+            FieldType::Text => write!(f, "text"),
+            FieldType::Date => write!(f, "date"),
+            FieldType::Iteration => write!(f, "iteration"),
+            FieldType::Other => write!(f, "unsupported type"),
+        }
+    }
+}
+
+/// Definition of a single field of the project
+#[derive(Debug, Deserialize)]
+pub struct FieldDefinition {
+    pub name: String,
+    #[serde(rename = "dataType")]
+    pub data_type: FieldType,
+}
+
+#[derive(Debug)]
+pub enum FieldMappingError {
+    /// The configured field name does not exist in the project
+    Missing { key: &'static str, name: String },
+    /// The field exists but does not hold the type the generator expects
+    TypeMismatch {
+        key: &'static str,
+        name: String,
+        expected: FieldType,
+        found: FieldType,
+    },
+}
+
+impl Display for FieldMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldMappingError::Missing { key, name } => write!(
+                f,
+                "Field \"{}\" mapped to `{}` does not exist in the project",
+                name, key
+            ),
+            FieldMappingError::TypeMismatch {
+                key,
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Field \"{}\" mapped to `{}` should be a {} field but is a {} field",
+                name, key, expected, found
+            ),
+        }
+    }
+}
+
+/// Looks up `name` in the project definitions and checks its type
+pub fn check_field(
+    definitions: &[FieldDefinition],
+    key: &'static str,
+    name: &str,
+    expected: FieldType,
+) -> Result<(), FieldMappingError> {
+    let definition =
+        definitions
+            .iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| FieldMappingError::Missing {
+                key,
+                name: name.to_string(),
+            })?;
+
+    if definition.data_type != expected {
+        return Err(FieldMappingError::TypeMismatch {
+            key,
+            name: name.to_string(),
+            expected,
+            found: definition.data_type,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definitions() -> Vec<FieldDefinition> {
+        serde_json::from_str(
+            r#"[
+                {"name": "Working Days", "dataType": "TEXT"},
+                {"name": "Estimate", "dataType": "NUMBER"},
+                {"name": "Section", "dataType": "SINGLE_SELECT"},
+                {"name": "Sprint", "dataType": "ITERATION"},
+                {"name": "Linked pull requests", "dataType": "LINKED_PULL_REQUESTS"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn missing_fields_are_reported() {
+        let error = check_field(&definitions(), "status", "Status", FieldType::SingleSelect);
+
+        assert!(matches!(
+            error,
+            Err(FieldMappingError::Missing { key: "status", ref name }) if name == "Status"
+        ));
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "Field \"Status\" mapped to `status` does not exist in the project"
+        );
+    }
+
+    #[test]
+    fn fields_of_the_wrong_type_are_reported() {
+        let error = check_field(
+            &definitions(),
+            "working_days",
+            "Working Days",
+            FieldType::Number,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error,
+            FieldMappingError::TypeMismatch {
+                expected: FieldType::Number,
+                found: FieldType::Text,
+                ..
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Field \"Working Days\" mapped to `working_days` should be a number field but is a \
+             text field"
+        );
+    }
+
+    #[test]
+    fn fields_of_the_expected_type_are_accepted() {
+        let definitions = definitions();
+
+        assert!(check_field(&definitions, "working_days", "Estimate", FieldType::Number).is_ok());
+        assert!(check_field(&definitions, "section", "Section", FieldType::SingleSelect).is_ok());
+        assert!(check_field(&definitions, "sprint", "Sprint", FieldType::Iteration).is_ok());
+        assert!(definitions
+            .iter()
+            .any(|definition| definition.data_type == FieldType::Other));
+    }
+}
@@ -1,6 +1,9 @@
 pub mod card;
+pub mod fields;
 
-use card::{ProjectCard, ProjectItems};
+use async_trait::async_trait;
+use card::{ProjectItems, RateLimit};
+use fields::{FieldDefinition, FieldMappingError, FieldType};
 
 use colored::Colorize;
 use reqwest::{
@@ -8,139 +11,1061 @@ use reqwest::{
     Client, ClientBuilder, StatusCode,
 };
 use serde::Serialize;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    cache::CacheMode,
+    config::{FieldsConfig, GithubConfig, GithubProject, OwnerKind},
+    source::{
+        card::{ItemType, ProjectCard},
+        CardSource, SourceError, REQUEST_TIMEOUT,
+    },
+};
 
 const ENDPOINT: &str = "https://api.github.com/graphql";
+/// Number of retries for a request before giving up
+const MAX_RETRIES: u32 = 5;
+/// Delay before the first retry, doubled on every subsequent attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Minimum delay after hitting a secondary rate limit which gives no retry time, as documented by
+/// github
+const SECONDARY_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
 
 // Note: Maybe put this somewhere else in the future
-// the endCursor will also need to be used too at some point in order to fully support pagination
+// $OWNER_ROOT is replaced by either `organization` or `user` depending on the owner kind
 const CARDS_QUERY: &str = r#"
-{
-    organization(login: "Autogrower") {
-        projectV2(number: 18) {
-            items(first: 100) {
+query(
+    $owner: String!,
+    $project: Int!,
+    $cursor: String,
+    $workingDaysField: String!,
+    $sectionField: String!,
+    $subSectionField: String!,
+    $statusField: String!
+) {
+    rateLimit {
+        cost
+        remaining
+        resetAt
+    }
+    $OWNER_ROOT(login: $owner) {
+        projectV2(number: $project) {
+            items(first: 100, after: $cursor) {
                 totalCount
                 nodes {
+                    isArchived
                     content {
+                        __typename
                         ... on DraftIssue {
+                            id
                             title
                             body
-                            assignees(first: 10) {
+                            assignees(first: 100) {
                                 nodes {
                                     login
+                                    name
                                 }
                             }
                         }
                         ... on Issue {
+                            id
+                            number
+                            title
+                            body
+                            url
+                            state
+                            repository {
+                                nameWithOwner
+                            }
+                            trackedIssues(first: 50) {
+                                nodes {
+                                    url
+                                }
+                            }
+                            subIssues(first: 50) {
+                                nodes {
+                                    url
+                                }
+                            }
+                            assignees(first: 100) {
+                                nodes {
+                                    login
+                                    name
+                                }
+                            }
+                            labels(first: 20) {
+                                nodes {
+                                    name
+                                }
+                            }
+                        }
+                        ... on PullRequest {
+                            id
+                            number
                             title
                             body
-                            assignees(first: 10) {
+                            url
+                            state
+                            repository {
+                                nameWithOwner
+                            }
+                            assignees(first: 100) {
                                 nodes {
                                     login
+                                    name
+                                }
+                            }
+                            labels(first: 20) {
+                                nodes {
+                                    name
                                 }
                             }
                         }
                     }
-                    working_days: fieldValueByName(name: "Working Days") {
+                    working_days: fieldValueByName(name: $workingDaysField) {
                         ... on ProjectV2ItemFieldNumberValue {
                             number
                         }
                     }
-                    section: fieldValueByName(name: "Section") {
+                    section: fieldValueByName(name: $sectionField) {
                         ... on ProjectV2ItemFieldSingleSelectValue {
                             name
                         }
                     }
-                    status: fieldValueByName(name: "Status") {
+                    status: fieldValueByName(name: $statusField) {
                         ... on ProjectV2ItemFieldSingleSelectValue {
                             name
                         }
                     }
-                    sub_section: fieldValueByName(name: "Sub-Section") {
+                    sub_section: fieldValueByName(name: $subSectionField) {
                         ... on ProjectV2ItemFieldSingleSelectValue {
                             name
                         }
                     }
+                    fieldValues(first: 50) {
+                        nodes {
+                            __typename
+                            ... on ProjectV2ItemFieldTextValue {
+                                text
+                                field { ...FieldName }
+                            }
+                            ... on ProjectV2ItemFieldNumberValue {
+                                number
+                                field { ...FieldName }
+                            }
+                            ... on ProjectV2ItemFieldDateValue {
+                                date
+                                field { ...FieldName }
+                            }
+                            ... on ProjectV2ItemFieldSingleSelectValue {
+                                name
+                                field { ...FieldName }
+                            }
+                            ... on ProjectV2ItemFieldIterationValue {
+                                title
+                                field { ...FieldName }
+                            }
+                        }
+                    }
                 }
                 pageInfo {
+                    hasNextPage
                     endCursor
                 }
             }
         }
     }
 }
+
+fragment FieldName on ProjectV2FieldConfiguration {
+    ... on ProjectV2FieldCommon {
+        name
+    }
+}
+"#;
+
+const UPDATE_DRAFT_ISSUE_MUTATION: &str = r#"
+mutation($id: ID!, $body: String!) {
+    updateProjectV2DraftIssue(input: { draftIssueId: $id, body: $body }) {
+        draftIssue {
+            id
+        }
+    }
+}
+"#;
+
+const UPDATE_ISSUE_MUTATION: &str = r#"
+mutation($id: ID!, $body: String!) {
+    updateIssue(input: { id: $id, body: $body }) {
+        issue {
+            id
+        }
+    }
+}
+"#;
+
+const UPDATE_PULL_REQUEST_MUTATION: &str = r#"
+mutation($id: ID!, $body: String!) {
+    updatePullRequest(input: { pullRequestId: $id, body: $body }) {
+        pullRequest {
+            id
+        }
+    }
+}
+"#;
+
+const FIELDS_QUERY: &str = r#"
+query($owner: String!, $project: Int!) {
+    $OWNER_ROOT(login: $owner) {
+        projectV2(number: $project) {
+            fields(first: 100) {
+                nodes {
+                    ... on ProjectV2FieldCommon {
+                        name
+                        dataType
+                    }
+                }
+            }
+        }
+    }
+}
 "#;
 
+#[derive(Debug)]
+pub enum GithubError {
+    /// The request could not be sent or the response could not be read
+    Transport(reqwest::Error),
+    /// The API answered with an unexpected status code
+    HttpStatus { status: StatusCode, message: String },
+    /// The query was rejected, contains the messages of the graphql `errors` array
+    GraphQl(Vec<String>),
+    /// The token is invalid or lacks the required permissions
+    Permission(Vec<String>),
+    /// The response does not have the expected shape
+    Schema(serde_json::Error),
+    /// The configured field mapping does not match the project fields
+    FieldMapping(Vec<FieldMappingError>),
+    /// A recorded response could not be read or written
+    Cache(std::io::Error),
+}
+
+impl Display for GithubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GithubError::Transport(e) => write!(f, "Github request failed : {}", e),
+            GithubError::HttpStatus { status, message } => {
+                write!(f, "Github answered with status {} : {}", status, message)
+            }
+            GithubError::GraphQl(messages) => {
+                write!(f, "Github rejected the query :")?;
+                for message in messages {
+                    write!(f, "\n  - {}", message)?;
+                }
+                Ok(())
+            }
+            GithubError::Permission(messages) => {
+                write!(
+                    f,
+                    "The github token is invalid or lacks the required permissions :"
+                )?;
+                for message in messages {
+                    write!(f, "\n  - {}", message)?;
+                }
+                Ok(())
+            }
+            GithubError::Schema(e) => {
+                write!(
+                    f,
+                    "Github response does not match the expected format : {}",
+                    e
+                )
+            }
+            GithubError::FieldMapping(errors) => {
+                write!(f, "The github field mapping does not match the project :")?;
+                for error in errors {
+                    write!(f, "\n  - {}", error)?;
+                }
+                Ok(())
+            }
+            GithubError::Cache(e) => write!(f, "Recorded github response unavailable : {}", e),
+        }
+    }
+}
+
+impl Error for GithubError {}
+
 /// Main client struct for all requests relevent to github projects
 pub struct ProjectsClient {
     /// Api key for request authentication
     ///
-    /// Permissions required (read) :
+    /// Permissions required (read, or read and write to push the fixes of the `fix` command) :
     /// - Repo
     ///   - Issues
+    ///   - Pull requests (only for the `fix` command)
     ///   - Metadata (required by issues)
     /// - Organization
     ///   - Projects
     ///
     /// Note: The token needs to be a fine grained token as classic tokens
     /// do not work with the github graphQL API
-    project: u8,
     client: Client,
+    /// Login of the organization or user owning the project
+    owner: String,
+    owner_kind: OwnerKind,
+    project: u32,
+    /// Prepended to the section of every card
+    section_prefix: Option<String>,
+    /// Replaces the section of every card
+    section_override: Option<String>,
+    /// Project field names holding the card information
+    fields: FieldsConfig,
+    /// Rate limit budget reported by the last cards query
+    rate_limit: Mutex<Option<RateLimit>>,
+    cache: CacheMode,
+    /// Url of the graphql API, only changed by tests
+    endpoint: String,
 }
 
 #[derive(Serialize)]
-struct GqlQuery {
+struct GqlQuery<V: Serialize> {
     query: String,
+    variables: V,
+}
+
+#[derive(Serialize)]
+struct ProjectVariables<'a> {
+    owner: &'a str,
+    project: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateBodyVariables<'a> {
+    id: &'a str,
+    body: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CardsVariables<'a> {
+    owner: &'a str,
+    project: u32,
+    cursor: Option<&'a str>,
+    working_days_field: &'a str,
+    section_field: &'a str,
+    sub_section_field: &'a str,
+    status_field: &'a str,
 }
 
 impl ProjectsClient {
-    pub fn new(api_key: &str, project: u8) -> ProjectsClient {
+    pub fn new(conf: &GithubConfig, project: &GithubProject, cache: CacheMode) -> ProjectsClient {
         let mut headers = HeaderMap::with_capacity(2);
         headers.insert(
             "Authorization",
-            HeaderValue::from_str(&format!("Bearer {}", api_key)).unwrap(),
+            HeaderValue::from_str(&format!("Bearer {}", conf.api_key)).unwrap(),
         );
         headers.insert("User-Agent", HeaderValue::from_static("pld-generator"));
 
         ProjectsClient {
-            project,
             client: ClientBuilder::new()
                 .default_headers(headers)
+                .timeout(REQUEST_TIMEOUT)
                 .build()
                 .unwrap(),
+            owner: project.owner.clone().unwrap_or_else(|| conf.owner.clone()),
+            owner_kind: project.owner_kind.unwrap_or(conf.owner_kind),
+            project: project.number,
+            section_prefix: project.section_prefix.clone(),
+            section_override: project.section.clone(),
+            fields: conf.fields.clone(),
+            rate_limit: Mutex::new(None),
+            cache,
+            endpoint: ENDPOINT.to_string(),
+        }
+    }
+
+    /// Rate limit budget reported by the last cards query, if any
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.lock().unwrap().clone()
+    }
+
+    /// Name of a response when recording or replaying, unique across projects
+    fn recording_name(&self, query: &str, page_index: usize) -> String {
+        format!(
+            "github_{}_{}_{}_{}.json",
+            self.owner, self.project, query, page_index
+        )
+    }
+
+    /// Root field of the graphql query matching the kind of the project owner
+    fn owner_root(&self) -> &'static str {
+        match self.owner_kind {
+            OwnerKind::Organization => "organization",
+            OwnerKind::User => "user",
+        }
+    }
+
+    fn project_url(&self) -> String {
+        let owner_path = match self.owner_kind {
+            OwnerKind::Organization => "orgs",
+            OwnerKind::User => "users",
+        };
+
+        format!(
+            "https://github.com/{}/{}/projects/{}",
+            owner_path, self.owner, self.project
+        )
+    }
+
+    /// Returns true if `name` is one of the fields with a dedicated place on the card
+    fn is_mapped_field(&self, name: &str) -> bool {
+        name == "Title"
+            || name == self.fields.working_days
+            || name == self.fields.section
+            || name == self.fields.sub_section
+            || name == self.fields.status
+    }
+
+    /// Sends a graphql query and returns the `data` field of the response
+    ///
+    /// `name` identifies the response when recording or replaying
+    async fn query<V: Serialize>(
+        &self,
+        name: &str,
+        query: &str,
+        variables: V,
+    ) -> Result<serde_json::Value, GithubError> {
+        let json_resp = match self.cache.replay(name).map_err(GithubError::Cache)? {
+            Some(recorded) => serde_json::from_slice::<serde_json::Value>(&recorded)
+                .map_err(GithubError::Schema)?,
+            None => {
+                let query = GqlQuery {
+                    query: query.replace("$OWNER_ROOT", self.owner_root()),
+                    variables,
+                };
+                let json_resp = self.fetch(&query).await?;
+
+                self.cache
+                    .record(name, json_resp.to_string().as_bytes())
+                    .map_err(GithubError::Cache)?;
+
+                json_resp
+            }
+        };
+
+        self.response_data(json_resp)
+    }
+
+    /// Sends a mutation, which is never recorded nor replayed
+    async fn mutate<V: Serialize>(
+        &self,
+        mutation: &str,
+        variables: V,
+    ) -> Result<serde_json::Value, GithubError> {
+        let json_resp = self
+            .fetch(&GqlQuery {
+                query: mutation.to_string(),
+                variables,
+            })
+            .await?;
+
+        self.response_data(json_resp)
+    }
+
+    /// Extracts the `data` of a graphql response, turning its `errors` into a [`GithubError`]
+    ///
+    /// Errors on single project items are left out, their content is null and they are skipped.
+    fn response_data(
+        &self,
+        mut json_resp: serde_json::Value,
+    ) -> Result<serde_json::Value, GithubError> {
+        let item_error = |error: &serde_json::Value| {
+            error["path"]
+                .as_array()
+                .is_some_and(|path| path.iter().any(|segment| segment == "nodes"))
+        };
+
+        if let Some(errors) = json_resp["errors"]
+            .as_array()
+            .filter(|errors| json_resp["data"].is_null() || !errors.iter().all(item_error))
+        {
+            let messages: Vec<String> = errors
+                .iter()
+                .map(|e| e["message"].as_str().unwrap_or_default().to_string())
+                .collect();
+            let permission_error = errors.iter().any(|e| {
+                matches!(
+                    e["type"].as_str(),
+                    Some("FORBIDDEN") | Some("INSUFFICIENT_SCOPES")
+                )
+            });
+
+            if permission_error {
+                return Err(GithubError::Permission(messages));
+            }
+            if !messages.is_empty() {
+                return Err(GithubError::GraphQl(messages));
+            }
+        }
+
+        if let Ok(rate_limit) = serde_json::from_value(json_resp["data"]["rateLimit"].clone()) {
+            *self.rate_limit.lock().unwrap() = Some(rate_limit);
+        }
+
+        Ok(json_resp["data"].take())
+    }
+
+    /// Sends the query over the network and returns the raw json response
+    async fn fetch<V: Serialize>(
+        &self,
+        query: &GqlQuery<V>,
+    ) -> Result<serde_json::Value, GithubError> {
+        let (status, body) = self.send_with_retry(query).await?;
+
+        let json_resp: serde_json::Value = match serde_json::from_str(&body) {
+            Ok(json_resp) => json_resp,
+            Err(_) if status != StatusCode::OK => {
+                return Err(GithubError::HttpStatus {
+                    status,
+                    message: body,
+                })
+            }
+            Err(e) => return Err(GithubError::Schema(e)),
+        };
+
+        match status {
+            StatusCode::OK => (),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(GithubError::Permission(vec![json_resp["message"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string()]))
+            }
+            _ => {
+                return Err(GithubError::HttpStatus {
+                    status,
+                    message: json_resp["message"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                })
+            }
+        }
+
+        Ok(json_resp)
+    }
+
+    /// Sends the query, retrying with an exponential backoff on timeouts, server errors and rate
+    /// limits, returns the status and body of the last response
+    async fn send_with_retry<V: Serialize>(
+        &self,
+        query: &GqlQuery<V>,
+    ) -> Result<(StatusCode, String), GithubError> {
+        let mut backoff = INITIAL_BACKOFF;
+
+        for attempt in 1..=MAX_RETRIES + 1 {
+            let result = self.client.post(&self.endpoint).json(query).send().await;
+            let last_attempt = attempt > MAX_RETRIES;
+
+            let delay = match result {
+                Err(e) if e.is_timeout() && !last_attempt => backoff,
+                Err(e) => return Err(GithubError::Transport(e)),
+                Ok(resp) => {
+                    let status = resp.status();
+                    let headers = resp.headers().clone();
+                    let body = resp.text().await.map_err(GithubError::Transport)?;
+
+                    match retry_delay(status, &headers, &body, backoff) {
+                        Some(delay) if !last_attempt => delay,
+                        _ => return Ok((status, body)),
+                    }
+                }
+            };
+
+            println!(
+                "{} Github request failed, retrying in {}s ({}/{})",
+                "WARNING:".yellow(),
+                delay.as_secs(),
+                attempt,
+                MAX_RETRIES
+            );
+
+            tokio::time::sleep(delay).await;
+            backoff *= 2;
         }
+
+        unreachable!("the last attempt always returns")
     }
 
-    pub async fn get_cards(&self) -> Vec<ProjectCard> {
-        let query_str = String::from(CARDS_QUERY)
-            .replace("$PROJECT", &self.project.to_string())
-            .replace("$CARD_COUNT", &100.to_string());
+    /// Checks that every configured field exists in the project and holds the expected type
+    pub async fn validate_fields(&self) -> Result<(), GithubError> {
+        let mut data = self
+            .query(
+                &self.recording_name("fields", 0),
+                FIELDS_QUERY,
+                ProjectVariables {
+                    owner: &self.owner,
+                    project: self.project,
+                },
+            )
+            .await?;
+
+        let definitions: Vec<FieldDefinition> =
+            serde_json::from_value(data[self.owner_root()]["projectV2"]["fields"]["nodes"].take())
+                .map_err(GithubError::Schema)?;
+
+        let expected = [
+            ("working_days", &self.fields.working_days, FieldType::Number),
+            ("section", &self.fields.section, FieldType::SingleSelect),
+            (
+                "sub_section",
+                &self.fields.sub_section,
+                FieldType::SingleSelect,
+            ),
+            ("status", &self.fields.status, FieldType::SingleSelect),
+        ];
+
+        let errors: Vec<FieldMappingError> = expected
+            .into_iter()
+            .filter_map(|(key, name, expected_type)| {
+                fields::check_field(&definitions, key, name, expected_type).err()
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(GithubError::FieldMapping(errors))
+        }
+    }
+
+    /// Fetches every card of the project, following the pagination cursor until exhausted
+    pub async fn get_cards(&self) -> Result<Vec<ProjectCard>, GithubError> {
+        let mut cards = Vec::new();
+        let mut unreadable = 0;
+        let mut cursor: Option<String> = None;
+        let mut page_index = 0;
+
+        let total_count = loop {
+            let page = self.get_cards_page(cursor.as_deref(), page_index).await?;
+            page_index += 1;
+
+            let items = page.nodes.len();
+            let page_cards: Vec<ProjectCard> = page.nodes.into_iter().filter_map(|i| i.0).collect();
+            unreadable += items - page_cards.len();
+
+            cards.extend(page_cards.into_iter().map(|mut card| {
+                // Mapped fields and the title already have a dedicated place on the card
+                card.fields.retain(|name, _| !self.is_mapped_field(name));
 
-        let resp = self
-            .client
-            .post(ENDPOINT)
-            .json(&GqlQuery { query: query_str })
-            .send()
+                // Draft issues have no page of their own
+                card.url.get_or_insert_with(|| self.project_url());
+
+                if let Some(section) = &self.section_override {
+                    card.section = section.clone();
+                }
+                if let Some(prefix) = &self.section_prefix {
+                    card.section = format!("{}{}", prefix, card.section);
+                }
+
+                card
+            }));
+
+            match page.paging_info.end_cursor {
+                Some(end_cursor) if page.paging_info.has_next_page => cursor = Some(end_cursor),
+                _ => break page.total_count,
+            }
+        };
+
+        if unreadable > 0 {
+            println!(
+                "{} {} item(s) of {} could not be read and were skipped, the token may lack \
+                 access to their repository",
+                "WARNING:".yellow(),
+                unreadable,
+                self.name()
+            );
+        }
+
+        if cards.len() + unreadable != total_count {
+            println!(
+                "{} Fetched {} cards but the project reports {} items",
+                "WARNING:".yellow(),
+                cards.len(),
+                total_count
+            );
+        }
+
+        Ok(cards)
+    }
+
+    /// Replaces the body of the draft issue, issue or pull request with the given content id
+    pub async fn update_body(
+        &self,
+        id: &str,
+        item_type: ItemType,
+        body: &str,
+    ) -> Result<(), GithubError> {
+        let mutation = match item_type {
+            ItemType::Draft => UPDATE_DRAFT_ISSUE_MUTATION,
+            ItemType::Issue => UPDATE_ISSUE_MUTATION,
+            ItemType::PullRequest => UPDATE_PULL_REQUEST_MUTATION,
+        };
+
+        self.mutate(mutation, UpdateBodyVariables { id, body })
             .await
-            .expect("Error sending cards graphql request");
+            .map(|_| ())
+    }
+
+    /// Fetches a single page of cards starting after `cursor`
+    async fn get_cards_page(
+        &self,
+        cursor: Option<&str>,
+        page_index: usize,
+    ) -> Result<ProjectItems, GithubError> {
+        let variables = CardsVariables {
+            owner: &self.owner,
+            project: self.project,
+            cursor,
+            working_days_field: &self.fields.working_days,
+            section_field: &self.fields.section,
+            sub_section_field: &self.fields.sub_section,
+            status_field: &self.fields.status,
+        };
+
+        let mut data = self
+            .query(
+                &self.recording_name("cards", page_index),
+                CARDS_QUERY,
+                variables,
+            )
+            .await?;
+
+        serde_json::from_value(data[self.owner_root()]["projectV2"]["items"].take())
+            .map_err(GithubError::Schema)
+    }
+}
+
+/// Delay before retrying a response indicating a transient failure, `None` for any other response
+///
+/// Secondary rate limits are reported as 403 or 429, either with a `retry-after` header, with an
+/// exhausted `x-ratelimit-remaining` along with the `x-ratelimit-reset` time, or only with a
+/// message in the body.
+fn retry_delay(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+    backoff: Duration,
+) -> Option<Duration> {
+    let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+
+    let retry_after = header("retry-after").map(Duration::from_secs);
+    let reset = match header("x-ratelimit-remaining") {
+        Some(0) => header("x-ratelimit-reset").map(|reset| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            // The reset time is rounded down to the second
+            Duration::from_secs(reset.saturating_sub(now) + 1)
+        }),
+        _ => None,
+    };
+
+    match status {
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => {
+            Some(retry_after.unwrap_or(backoff))
+        }
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+            let secondary_limit = body.to_lowercase().contains("secondary rate limit");
+
+            retry_after.or(reset).or_else(|| {
+                (secondary_limit || status == StatusCode::TOO_MANY_REQUESTS)
+                    .then(|| backoff.max(SECONDARY_RATE_LIMIT_BACKOFF))
+            })
+        }
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl CardSource for ProjectsClient {
+    fn name(&self) -> String {
+        format!("github project {}/{}", self.owner, self.project)
+    }
 
-        let status = resp.status();
+    async fn validate(&self) -> Result<(), SourceError> {
+        self.validate_fields().await.map_err(SourceError::Github)
+    }
 
-        let json_resp: serde_json::Value = resp
-            .json::<serde_json::Value>()
+    async fn get_cards(&self) -> Result<Vec<ProjectCard>, SourceError> {
+        ProjectsClient::get_cards(self)
             .await
-            .expect("Error deserializing cards json response");
+            .map_err(SourceError::Github)
+    }
+
+    async fn update_body(&self, card: &ProjectCard, body: &str) -> Result<(), SourceError> {
+        let id = card
+            .id
+            .as_deref()
+            .ok_or_else(|| SourceError::ReadOnly(self.name()))?;
 
-        if status != StatusCode::OK {
-            println!("{}", "Error while fetching the github cards, this is most likely an authentication issue.".to_string().red());
-            println!("Error message : {}", json_resp["message"].to_string());
+        ProjectsClient::update_body(self, id, card.item_type, body)
+            .await
+            .map_err(SourceError::Github)
+    }
 
-            return Vec::new();
+    fn report(&self) {
+        if let Some(rate_limit) = self.rate_limit() {
+            println!(
+                "Github API budget after {} : {} points remaining, last query cost {} (resets at {})",
+                self.name(),
+                rate_limit.remaining,
+                rate_limit.cost,
+                rate_limit.reset_at
+            );
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderName;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{header, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn headers(pairs: &[(&'static str, String)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    HeaderName::from_static(name),
+                    HeaderValue::from_str(value).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn retry_after_header_is_followed() {
+        let headers = headers(&[("retry-after", "12".to_string())]);
+
+        assert_eq!(
+            retry_delay(StatusCode::FORBIDDEN, &headers, "", INITIAL_BACKOFF),
+            Some(Duration::from_secs(12))
+        );
+    }
+
+    #[test]
+    fn exhausted_rate_limit_waits_for_the_reset() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let headers = headers(&[
+            ("x-ratelimit-remaining", "0".to_string()),
+            ("x-ratelimit-reset", (now.as_secs() + 30).to_string()),
+        ]);
+
+        let delay = retry_delay(StatusCode::FORBIDDEN, &headers, "", INITIAL_BACKOFF).unwrap();
+        assert!((30..=31).contains(&delay.as_secs()));
+    }
+
+    #[test]
+    fn secondary_rate_limit_message_backs_off_a_minute() {
+        let body = r#"{"message": "You have exceeded a secondary rate limit."}"#;
+
+        assert_eq!(
+            retry_delay(
+                StatusCode::FORBIDDEN,
+                &HeaderMap::new(),
+                body,
+                INITIAL_BACKOFF
+            ),
+            Some(SECONDARY_RATE_LIMIT_BACKOFF)
+        );
+    }
+
+    #[test]
+    fn permission_errors_are_not_retried() {
+        let body = r#"{"message": "Resource not accessible by personal access token"}"#;
+
+        assert_eq!(
+            retry_delay(
+                StatusCode::FORBIDDEN,
+                &HeaderMap::new(),
+                body,
+                INITIAL_BACKOFF
+            ),
+            None
+        );
+        assert_eq!(
+            retry_delay(StatusCode::OK, &HeaderMap::new(), "{}", INITIAL_BACKOFF),
+            None
+        );
+    }
+
+    #[test]
+    fn server_errors_use_the_backoff() {
+        assert_eq!(
+            retry_delay(
+                StatusCode::BAD_GATEWAY,
+                &HeaderMap::new(),
+                "",
+                INITIAL_BACKOFF
+            ),
+            Some(INITIAL_BACKOFF)
+        );
+    }
 
-        let parsed_resp: ProjectItems =
-            serde_json::from_value(json_resp["data"]["organization"]["projectV2"]["items"].clone())
-                .expect("Error deserializing json response");
+    fn client(server: &MockServer) -> ProjectsClient {
+        let conf: GithubConfig =
+            toml::from_str("api_key = \"secret\"\nowner = \"Autogrower\"\nproject_number = 1")
+                .unwrap();
 
-        parsed_resp.nodes
+        let mut client = ProjectsClient::new(&conf, &conf.all_projects()[0], CacheMode::Live);
+        client.endpoint = server.uri();
+        client
+    }
+
+    async fn respond(server: &MockServer, response: ResponseTemplate) {
+        Mock::given(method("POST"))
+            .and(header("Authorization", "Bearer secret"))
+            .respond_with(response)
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    fn items(nodes: serde_json::Value) -> serde_json::Value {
+        json!({
+            "organization": {
+                "projectV2": {
+                    "items": {
+                        "totalCount": nodes.as_array().unwrap().len(),
+                        "nodes": nodes,
+                        "pageInfo": { "hasNextPage": false, "endCursor": null }
+                    }
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn graphql_errors_are_reported() {
+        let server = MockServer::start().await;
+        respond(
+            &server,
+            ResponseTemplate::new(200).set_body_json(json!({
+                "data": null,
+                "errors": [{ "type": "NOT_FOUND", "message": "Could not resolve to a ProjectV2" }]
+            })),
+        )
+        .await;
+
+        let error = client(&server).get_cards().await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            GithubError::GraphQl(ref messages) if messages == &["Could not resolve to a ProjectV2"]
+        ));
+    }
+
+    #[tokio::test]
+    async fn forbidden_graphql_errors_are_permission_errors() {
+        let server = MockServer::start().await;
+        respond(
+            &server,
+            ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "organization": { "projectV2": null } },
+                "errors": [{
+                    "type": "FORBIDDEN",
+                    "path": ["organization", "projectV2"],
+                    "message": "Resource not accessible by personal access token"
+                }]
+            })),
+        )
+        .await;
+
+        let error = client(&server).get_cards().await.unwrap_err();
+
+        assert!(matches!(error, GithubError::Permission(_)));
+    }
+
+    #[tokio::test]
+    async fn unauthorized_status_is_a_permission_error() {
+        let server = MockServer::start().await;
+        respond(
+            &server,
+            ResponseTemplate::new(401).set_body_json(json!({ "message": "Bad credentials" })),
+        )
+        .await;
+
+        let error = client(&server).get_cards().await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            GithubError::Permission(ref messages) if messages == &["Bad credentials"]
+        ));
+    }
+
+    #[tokio::test]
+    async fn forbidden_status_is_a_permission_error() {
+        let server = MockServer::start().await;
+        respond(
+            &server,
+            ResponseTemplate::new(403)
+                .set_body_json(json!({ "message": "Resource not accessible by integration" })),
+        )
+        .await;
+
+        let error = client(&server).get_cards().await.unwrap_err();
+
+        assert!(matches!(error, GithubError::Permission(_)));
+    }
+
+    #[tokio::test]
+    async fn unreadable_items_are_skipped() {
+        let server = MockServer::start().await;
+        respond(
+            &server,
+            ResponseTemplate::new(200).set_body_json(json!({
+                "data": items(json!([
+                    { "isArchived": false, "content": null, "fieldValues": { "nodes": [] } },
+                    {
+                        "isArchived": false,
+                        "content": {
+                            "__typename": "DraftIssue",
+                            "id": "DI_1",
+                            "title": "1.1 Login",
+                            "body": "",
+                            "assignees": { "nodes": [] }
+                        },
+                        "fieldValues": { "nodes": [] }
+                    }
+                ])),
+                "errors": [{
+                    "type": "FORBIDDEN",
+                    "path": ["organization", "projectV2", "items", "nodes", 0, "content"],
+                    "message": "Resource not accessible by personal access token"
+                }]
+            })),
+        )
+        .await;
+
+        let cards = client(&server).get_cards().await.unwrap();
+
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].name, "1.1 Login");
+        assert_eq!(
+            cards[0].url.as_deref(),
+            Some("https://github.com/orgs/Autogrower/projects/1")
+        );
     }
 }
@@ -0,0 +1,396 @@
+mod model;
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client, ClientBuilder, StatusCode, Url,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+};
+
+use model::{Board, Issue};
+
+use crate::{
+    cache::CacheMode,
+    config::GitlabConfig,
+    source::{
+        card::{IssueState, ItemType, ProjectCard},
+        CardSource, SourceError, REQUEST_TIMEOUT,
+    },
+};
+
+const API_PREFIX: [&str; 2] = ["api", "v4"];
+const PAGE_SIZE: usize = 100;
+/// Separator of gitlab scoped labels such as `Section::Backend`
+const SCOPE_SEPARATOR: &str = "::";
+
+#[derive(Debug)]
+pub enum GitlabError {
+    /// The configured gitlab url is not valid
+    InvalidUrl(String),
+    /// The request could not be sent or the response could not be read
+    Transport(reqwest::Error),
+    /// The API answered with an unexpected status code
+    HttpStatus { status: StatusCode, message: String },
+    /// The response does not have the expected shape
+    Schema(serde_json::Error),
+    /// A recorded response could not be read or written
+    Cache(std::io::Error),
+}
+
+impl Display for GitlabError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitlabError::InvalidUrl(url) => write!(f, "Invalid gitlab url : {}", url),
+            GitlabError::Transport(e) => write!(f, "Gitlab request failed : {}", e),
+            GitlabError::HttpStatus { status, message } => {
+                write!(f, "Gitlab answered with status {} : {}", status, message)
+            }
+            GitlabError::Schema(e) => {
+                write!(
+                    f,
+                    "Gitlab response does not match the expected format : {}",
+                    e
+                )
+            }
+            GitlabError::Cache(e) => write!(f, "Recorded gitlab response unavailable : {}", e),
+        }
+    }
+}
+
+impl Error for GitlabError {}
+
+/// Card source reading the issues of a gitlab project through the REST API
+///
+/// Scoped labels are used to map issues to cards, ie: `Section::Backend`,
+/// `Sub-Section::Authentication` and `Status::In Progress`
+pub struct GitlabClient {
+    client: Client,
+    conf: GitlabConfig,
+    cache: CacheMode,
+}
+
+impl GitlabClient {
+    pub fn new(conf: &GitlabConfig, cache: CacheMode) -> GitlabClient {
+        let mut headers = HeaderMap::with_capacity(1);
+        headers.insert("PRIVATE-TOKEN", HeaderValue::from_str(&conf.token).unwrap());
+
+        GitlabClient {
+            client: ClientBuilder::new()
+                .default_headers(headers)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap(),
+            conf: conf.clone(),
+            cache,
+        }
+    }
+
+    /// Builds the url of a project route, the project path is percent encoded as gitlab expects
+    fn project_url(&self, route: &[&str]) -> Result<Url, GitlabError> {
+        let mut url = Url::parse(&self.conf.url)
+            .map_err(|_| GitlabError::InvalidUrl(self.conf.url.clone()))?;
+
+        url.path_segments_mut()
+            .map_err(|_| GitlabError::InvalidUrl(self.conf.url.clone()))?
+            .pop_if_empty()
+            .extend(API_PREFIX)
+            .extend(["projects", &self.conf.project])
+            .extend(route);
+
+        Ok(url)
+    }
+
+    /// Sends a GET request and deserializes the response
+    ///
+    /// `name` identifies the response when recording or replaying
+    async fn get<T: DeserializeOwned>(&self, name: &str, url: Url) -> Result<T, GitlabError> {
+        let body = match self.cache.replay(name).map_err(GitlabError::Cache)? {
+            Some(recorded) => recorded,
+            None => {
+                let resp = self
+                    .client
+                    .get(url)
+                    .send()
+                    .await
+                    .map_err(GitlabError::Transport)?;
+
+                let status = resp.status();
+                let body = resp.bytes().await.map_err(GitlabError::Transport)?;
+
+                if status != StatusCode::OK {
+                    return Err(GitlabError::HttpStatus {
+                        status,
+                        message: String::from_utf8_lossy(&body).to_string(),
+                    });
+                }
+
+                self.cache.record(name, &body).map_err(GitlabError::Cache)?;
+
+                body.to_vec()
+            }
+        };
+
+        serde_json::from_slice(&body).map_err(GitlabError::Schema)
+    }
+
+    /// Query parameters filtering the issues, taken from the board scope when a board is set
+    async fn issue_filters(&self) -> Result<Vec<(&'static str, String)>, GitlabError> {
+        let mut milestone = self.conf.milestone.clone();
+        let mut labels = self.conf.labels.clone();
+
+        if let Some(board_id) = self.conf.board_id {
+            let url = self.project_url(&["boards", &board_id.to_string()])?;
+            let board: Board = self.get("gitlab_board.json", url).await?;
+
+            if let Some(board_milestone) = board.milestone {
+                milestone.get_or_insert(board_milestone.title);
+            }
+            labels.extend(board.labels.into_iter().map(|l| l.name));
+        }
+
+        let mut filters = vec![("state", "all".to_string())];
+        if let Some(milestone) = milestone {
+            filters.push(("milestone", milestone));
+        }
+        if !labels.is_empty() {
+            filters.push(("labels", labels.join(",")));
+        }
+
+        Ok(filters)
+    }
+
+    /// Fetches every issue matching the configured filters, page by page until a page is not full
+    pub async fn get_issues(&self) -> Result<Vec<Issue>, GitlabError> {
+        let filters = self.issue_filters().await?;
+        let mut issues = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let mut url = self.project_url(&["issues"])?;
+            url.query_pairs_mut()
+                .extend_pairs(filters.iter().map(|(k, v)| (*k, v.as_str())))
+                .append_pair("per_page", &PAGE_SIZE.to_string())
+                .append_pair("page", &page.to_string());
+
+            let page_issues: Vec<Issue> = self
+                .get(&format!("gitlab_issues_{}.json", page), url)
+                .await?;
+            let last_page = page_issues.len() < PAGE_SIZE;
+
+            issues.extend(page_issues);
+
+            if last_page {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(issues)
+    }
+
+    /// Returns the value of the scoped label `scope::value` of the issue
+    fn scoped_label<'a>(labels: &'a [String], scope: &str) -> Option<&'a str> {
+        labels.iter().find_map(|label| {
+            let (label_scope, value) = label.split_once(SCOPE_SEPARATOR)?;
+
+            label_scope
+                .eq_ignore_ascii_case(scope)
+                .then_some(value.trim())
+        })
+    }
+
+    fn to_card(&self, issue: Issue) -> ProjectCard {
+        let section = Self::scoped_label(&issue.labels, &self.conf.section_label);
+        let sub_section = Self::scoped_label(&issue.labels, &self.conf.sub_section_label);
+        // Fallback on the issue state when no status label is set
+        let status = Self::scoped_label(&issue.labels, &self.conf.status_label)
+            .map(str::to_string)
+            .unwrap_or_else(|| match issue.state.as_str() {
+                "closed" => "Done".to_string(),
+                _ => "Todo".to_string(),
+            });
+
+        let seconds_per_day = self.conf.hours_per_day * 3600.0;
+
+        ProjectCard {
+            section: section.unwrap_or_default().to_string(),
+            sub_section: sub_section.unwrap_or_default().to_string(),
+            status,
+            working_days: issue.time_stats.time_estimate as f32 / seconds_per_day,
+            content: issue.description.unwrap_or_default(),
+            assignees: issue.assignees.iter().map(|u| u.username.clone()).collect(),
+            assignee_names: issue
+                .assignees
+                .into_iter()
+                .filter_map(|u| Some((u.username, u.name?)))
+                .collect(),
+            fields: BTreeMap::new(),
+            state: Some(match issue.state.as_str() {
+                "closed" => IssueState::Closed,
+                _ => IssueState::Open,
+            }),
+            item_type: ItemType::Issue,
+            archived: false,
+            labels: issue.labels,
+            url: Some(issue.web_url.clone()),
+            id: None,
+            references: issue
+                .iid
+                .map(|iid| format!("#{}", iid))
+                .into_iter()
+                .chain([issue.web_url])
+                .collect(),
+            repository: None,
+            dependencies: Vec::new(),
+            linked_issues: Vec::new(),
+            name: issue.title,
+        }
+    }
+}
+
+#[async_trait]
+impl CardSource for GitlabClient {
+    fn name(&self) -> String {
+        format!("gitlab project {}", self.conf.project)
+    }
+
+    async fn get_cards(&self) -> Result<Vec<ProjectCard>, SourceError> {
+        let issues = self.get_issues().await.map_err(SourceError::Gitlab)?;
+
+        Ok(issues.into_iter().map(|i| self.to_card(i)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{header, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    const ISSUES_PATH: &str = "/api/v4/projects/group%2Fproject/issues";
+
+    fn client(server: &MockServer) -> GitlabClient {
+        let conf: GitlabConfig = toml::from_str(&format!(
+            "url = \"{}\"\ntoken = \"secret\"\nproject = \"group/project\"",
+            server.uri()
+        ))
+        .unwrap();
+
+        GitlabClient::new(&conf, CacheMode::Live)
+    }
+
+    fn issue(iid: u64, labels: &[&str], state: &str, time_estimate: u64) -> serde_json::Value {
+        json!({
+            "iid": iid,
+            "title": format!("Issue {}", iid),
+            "description": "# User wish",
+            "state": state,
+            "labels": labels,
+            "assignees": [{ "username": "nydragon", "name": "Nicolas" }],
+            "time_stats": { "time_estimate": time_estimate },
+            "web_url": format!("https://gitlab.com/group/project/-/issues/{}", iid),
+        })
+    }
+
+    async fn mock_page(server: &MockServer, page: usize, issues: Vec<serde_json::Value>) {
+        Mock::given(method("GET"))
+            .and(path(ISSUES_PATH))
+            .and(header("PRIVATE-TOKEN", "secret"))
+            .and(query_param("state", "all"))
+            .and(query_param("per_page", PAGE_SIZE.to_string()))
+            .and(query_param("page", page.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(issues))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn issues_are_fetched_until_a_page_is_not_full() {
+        let server = MockServer::start().await;
+        let issues: Vec<_> = (1..=PAGE_SIZE as u64 + 1)
+            .map(|iid| issue(iid, &[], "opened", 0))
+            .collect();
+
+        mock_page(&server, 1, issues[..PAGE_SIZE].to_vec()).await;
+        mock_page(&server, 2, issues[PAGE_SIZE..].to_vec()).await;
+
+        let cards = client(&server).get_cards().await.unwrap();
+
+        assert_eq!(cards.len(), PAGE_SIZE + 1);
+        assert_eq!(cards.last().unwrap().name, "Issue 101");
+    }
+
+    #[tokio::test]
+    async fn scoped_labels_give_the_section_and_status() {
+        let server = MockServer::start().await;
+        mock_page(
+            &server,
+            1,
+            vec![
+                issue(
+                    1,
+                    &[
+                        "Section::Backend",
+                        "sub-section:: Auth",
+                        "Status::In Progress",
+                    ],
+                    "opened",
+                    0,
+                ),
+                issue(2, &["bug"], "closed", 0),
+            ],
+        )
+        .await;
+
+        let cards = client(&server).get_cards().await.unwrap();
+
+        assert_eq!(cards[0].section, "Backend");
+        assert_eq!(cards[0].sub_section, "Auth");
+        assert_eq!(cards[0].status, "In Progress");
+        assert_eq!(cards[0].references[0], "#1");
+        assert_eq!(cards[0].assignee_names["nydragon"], "Nicolas");
+        // Without a status label, the issue state gives the status
+        assert_eq!(cards[1].section, "");
+        assert_eq!(cards[1].status, "Done");
+        assert_eq!(cards[1].state, Some(IssueState::Closed));
+    }
+
+    #[tokio::test]
+    async fn time_estimate_is_converted_to_working_days() {
+        let server = MockServer::start().await;
+        // 12 hours, a day and a half of 8 hours
+        mock_page(&server, 1, vec![issue(1, &[], "opened", 12 * 3600)]).await;
+
+        let cards = client(&server).get_cards().await.unwrap();
+
+        assert_eq!(cards[0].working_days, 1.5);
+    }
+
+    #[tokio::test]
+    async fn error_status_is_reported() {
+        let server = MockServer::start().await;
+        Mock::given(path(ISSUES_PATH))
+            .respond_with(ResponseTemplate::new(401).set_body_string("401 Unauthorized"))
+            .mount(&server)
+            .await;
+
+        let error = client(&server).get_issues().await.err().unwrap();
+
+        assert!(matches!(
+            error,
+            GitlabError::HttpStatus {
+                status: StatusCode::UNAUTHORIZED,
+                ..
+            }
+        ));
+    }
+}
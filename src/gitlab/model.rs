@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct User {
+    pub username: String,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TimeStats {
+    /// Estimate in seconds
+    pub time_estimate: u64,
+}
+
+#[derive(Deserialize)]
+pub struct Issue {
+    /// Number of the issue in its project, referenced as `#iid`
+    pub iid: Option<u64>,
+    pub title: String,
+    pub description: Option<String>,
+    /// Either `opened` or `closed`
+    pub state: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<User>,
+    pub time_stats: TimeStats,
+    pub web_url: String,
+}
+
+#[derive(Deserialize)]
+pub struct Milestone {
+    pub title: String,
+}
+
+#[derive(Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+/// Scope of a board, used to filter the issues
+#[derive(Deserialize)]
+pub struct Board {
+    pub milestone: Option<Milestone>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+
+use crate::parsing::PldCard;
+
+/// Dependencies between the cards of the PLD
+pub struct DependencyGraph {
+    /// Cards each card depends on, as indexes in the card list
+    edges: Vec<Vec<usize>>,
+    /// Dependencies closing a cycle, left out when looking for the critical path
+    back_edges: HashSet<(usize, usize)>,
+    /// Every cycle found, in dependency order, the first card depending on the second and so on
+    pub cycles: Vec<Vec<usize>>,
+    /// References matching no card, along with the index of the card holding them
+    pub dangling: Vec<(usize, String)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    InProgress,
+    Done,
+}
+
+impl DependencyGraph {
+    /// Resolves the dependencies of every card, a reference matches the references of a card or
+    /// its title ignoring case
+    ///
+    /// Linked issues matching no card are outside of the PLD and ignored.
+    pub fn new(cards: &[PldCard]) -> DependencyGraph {
+        let find = |reference: &str| {
+            cards.iter().position(|card| {
+                card.references.iter().any(|r| r == reference)
+                    || card.name.trim().eq_ignore_ascii_case(reference.trim())
+            })
+        };
+
+        let mut edges = vec![Vec::new(); cards.len()];
+        let mut dangling = Vec::new();
+
+        for (i, card) in cards.iter().enumerate() {
+            for reference in &card.dependencies {
+                match find(reference) {
+                    Some(j) if !edges[i].contains(&j) => edges[i].push(j),
+                    Some(_) => (),
+                    None => dangling.push((i, reference.clone())),
+                }
+            }
+
+            for j in card.linked_issues.iter().filter_map(|issue| find(issue)) {
+                if !edges[i].contains(&j) {
+                    edges[i].push(j);
+                }
+            }
+        }
+
+        let mut graph = DependencyGraph {
+            edges,
+            back_edges: HashSet::new(),
+            cycles: Vec::new(),
+            dangling,
+        };
+
+        let mut visits = vec![Visit::New; cards.len()];
+        for card in 0..cards.len() {
+            if visits[card] == Visit::New {
+                graph.visit(card, &mut visits, &mut Vec::new());
+            }
+        }
+
+        graph
+    }
+
+    /// Depth first search recording the edges going back to a card of the current path
+    fn visit(&mut self, card: usize, visits: &mut [Visit], path: &mut Vec<usize>) {
+        visits[card] = Visit::InProgress;
+        path.push(card);
+
+        for dependency in self.edges[card].clone() {
+            match visits[dependency] {
+                Visit::New => self.visit(dependency, visits, path),
+                Visit::InProgress => {
+                    self.back_edges.insert((card, dependency));
+
+                    let start = path.iter().position(|c| *c == dependency).unwrap();
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(dependency);
+                    self.cycles.push(cycle);
+                }
+                Visit::Done => (),
+            }
+        }
+
+        path.pop();
+        visits[card] = Visit::Done;
+    }
+
+    /// True when no card depends on another one
+    pub fn is_empty(&self) -> bool {
+        self.edges.iter().all(Vec::is_empty)
+    }
+
+    /// Cards the given card depends on
+    pub fn dependencies(&self, card: usize) -> &[usize] {
+        &self.edges[card]
+    }
+
+    /// Longest chain of dependencies in working days, from the first card to complete to the
+    /// last one, dependencies closing a cycle are ignored
+    pub fn critical_path(&self, cards: &[PldCard]) -> Vec<usize> {
+        // Working days of the longest chain ending with each card, and the previous card
+        let mut longest: Vec<Option<(f32, Option<usize>)>> = vec![None; cards.len()];
+
+        for card in 0..cards.len() {
+            self.longest_chain(card, cards, &mut longest);
+        }
+
+        let mut current = longest
+            .iter()
+            .enumerate()
+            .filter_map(|(card, chain)| Some((card, chain.as_ref()?.0)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(card, _)| card);
+
+        let mut path = Vec::new();
+        while let Some(card) = current {
+            path.push(card);
+            current = longest[card].and_then(|(_, previous)| previous);
+        }
+
+        path.reverse();
+        path
+    }
+
+    fn longest_chain(
+        &self,
+        card: usize,
+        cards: &[PldCard],
+        longest: &mut Vec<Option<(f32, Option<usize>)>>,
+    ) -> f32 {
+        if let Some((days, _)) = longest[card] {
+            return days;
+        }
+
+        let mut previous: Option<(f32, usize)> = None;
+        for &dependency in &self.edges[card] {
+            if self.back_edges.contains(&(card, dependency)) {
+                continue;
+            }
+
+            let days = self.longest_chain(dependency, cards, longest);
+            if previous.is_none_or(|(max, _)| days > max) {
+                previous = Some((days, dependency));
+            }
+        }
+
+        let days = cards[card].working_days + previous.map_or(0.0, |(days, _)| days);
+        longest[card] = Some((days, previous.map(|(_, dependency)| dependency)));
+
+        days
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::CardSchema,
+        source::card::{fixtures, ProjectCard},
+    };
+
+    /// Card of the `owner/<repository>` repository depending on the cards given in its body
+    fn card(repository: &str, number: u64, depends_on: &str, linked_issues: &[&str]) -> PldCard {
+        let repository = format!("owner/{}", repository);
+        let card = ProjectCard {
+            working_days: 1.0,
+            references: vec![format!("{}#{}", repository, number)],
+            repository: Some(repository.clone()),
+            linked_issues: linked_issues.iter().map(|s| s.to_string()).collect(),
+            ..fixtures::card(
+                &format!("{} {}", repository, number),
+                &format!("{}\n\nDepends on {}", fixtures::BODY, depends_on),
+            )
+        };
+
+        PldCard::new(&card, &CardSchema::default()).unwrap()
+    }
+
+    #[test]
+    fn short_references_point_to_the_repository_of_the_card() {
+        let cards = [
+            card("api", 12, "", &[]),
+            card("web", 12, "", &[]),
+            card("web", 1, "#12", &[]),
+            card("api", 1, "#12, owner/web#12", &[]),
+        ];
+        let graph = DependencyGraph::new(&cards);
+
+        assert_eq!(graph.dependencies(2), [1]);
+        assert_eq!(graph.dependencies(3), [0, 1]);
+        assert!(graph.dangling.is_empty());
+    }
+
+    #[test]
+    fn linked_issues_outside_of_the_pld_are_ignored() {
+        let cards = [
+            card("api", 1, "", &[]),
+            card(
+                "api",
+                2,
+                "#3",
+                &["owner/api#1", "https://github.com/owner/api/issues/9"],
+            ),
+        ];
+        let graph = DependencyGraph::new(&cards);
+
+        assert_eq!(graph.dependencies(1), [0]);
+        assert_eq!(graph.dangling, [(1, "owner/api#3".to_string())]);
+    }
+
+    #[test]
+    fn cycles_are_left_out_of_the_critical_path() {
+        let cards = [
+            card("api", 1, "#3", &[]),
+            card("api", 2, "#1", &[]),
+            card("api", 3, "#2", &[]),
+            card("api", 4, "", &[]),
+        ];
+        let graph = DependencyGraph::new(&cards);
+
+        assert_eq!(graph.cycles, [vec![0, 2, 1, 0]]);
+        assert_eq!(graph.critical_path(&cards).len(), 3);
+    }
+}
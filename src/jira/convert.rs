@@ -0,0 +1,391 @@
+use serde_json::Value;
+
+/// Converts an Atlassian document format description to markdown
+pub fn adf_to_markdown(doc: &Value) -> String {
+    let mut output = String::new();
+
+    write_blocks(&mut output, doc, 0);
+
+    output.trim().to_string()
+}
+
+fn children(node: &Value) -> impl Iterator<Item = &Value> {
+    node["content"].as_array().into_iter().flatten()
+}
+
+/// Writes block nodes separated by blank lines, `depth` is the list nesting level
+fn write_blocks(output: &mut String, node: &Value, depth: usize) {
+    for child in children(node) {
+        write_block(output, child, depth);
+    }
+}
+
+fn write_block(output: &mut String, node: &Value, depth: usize) {
+    match node["type"].as_str().unwrap_or_default() {
+        "paragraph" => {
+            output.push_str(&inline_text(node));
+            output.push_str("\n\n");
+        }
+        "heading" => {
+            let level = node["attrs"]["level"].as_u64().unwrap_or(1) as usize;
+            output.push_str(&format!("{} {}\n\n", "#".repeat(level), inline_text(node)));
+        }
+        "bulletList" | "orderedList" | "taskList" => {
+            write_list(output, node, &"  ".repeat(depth));
+            if depth == 0 {
+                output.push('\n');
+            }
+        }
+        "codeBlock" => {
+            let language = node["attrs"]["language"].as_str().unwrap_or_default();
+            output.push_str(&format!("```{}\n{}\n```\n\n", language, inline_text(node)));
+        }
+        "blockquote" => {
+            let mut quote = String::new();
+            write_blocks(&mut quote, node, depth);
+            for line in quote.trim().lines() {
+                output.push_str(&format!("> {}\n", line));
+            }
+            output.push('\n');
+        }
+        "rule" => output.push_str("---\n\n"),
+        _ => write_blocks(output, node, depth),
+    }
+}
+
+/// Writes the items of a list, `indentation` aligns them with the content of the parent item
+fn write_list(output: &mut String, node: &Value, indentation: &str) {
+    let ordered = node["type"] == "orderedList";
+
+    for (i, item) in children(node).enumerate() {
+        let marker = match item["type"].as_str().unwrap_or_default() {
+            "taskItem" if item["attrs"]["state"] == "DONE" => "- [x]".to_string(),
+            "taskItem" => "- [ ]".to_string(),
+            _ if ordered => format!("{}.", i + 1),
+            _ => "-".to_string(),
+        };
+
+        // Task items hold inline content directly while list items hold paragraphs
+        if item["type"] == "taskItem" {
+            output.push_str(&format!(
+                "{}{} {}\n",
+                indentation,
+                marker,
+                inline_text(item)
+            ));
+            continue;
+        }
+
+        // Nested blocks must start at the column of the item text to stay in the item
+        let content_indentation = format!("{}{}", indentation, " ".repeat(marker.len() + 1));
+
+        let mut first_line = true;
+        for child in children(item) {
+            match child["type"].as_str().unwrap_or_default() {
+                "bulletList" | "orderedList" | "taskList" => {
+                    write_list(output, child, &content_indentation)
+                }
+                _ => {
+                    let text = inline_text(child);
+                    if first_line {
+                        output.push_str(&format!("{}{} {}\n", indentation, marker, text));
+                        first_line = false;
+                    } else {
+                        output.push_str(&format!("{}{}\n", content_indentation, text));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Concatenates the inline content of a node, applying text marks
+fn inline_text(node: &Value) -> String {
+    children(node)
+        .map(|child| match child["type"].as_str().unwrap_or_default() {
+            "text" => apply_marks(child["text"].as_str().unwrap_or_default(), child),
+            "hardBreak" => "\n".to_string(),
+            "mention" => child["attrs"]["text"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            "emoji" => child["attrs"]["shortName"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            "inlineCard" => child["attrs"]["url"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            _ => inline_text(child),
+        })
+        .collect()
+}
+
+fn apply_marks(text: &str, node: &Value) -> String {
+    let mut output = text.to_string();
+
+    for mark in node["marks"].as_array().into_iter().flatten() {
+        output = match mark["type"].as_str().unwrap_or_default() {
+            "strong" => format!("**{}**", output),
+            "em" => format!("*{}*", output),
+            "code" => format!("`{}`", output),
+            "strike" => format!("~~{}~~", output),
+            "link" => format!(
+                "[{}]({})",
+                output,
+                mark["attrs"]["href"].as_str().unwrap_or_default()
+            ),
+            _ => output,
+        };
+    }
+
+    output
+}
+
+/// Converts a jira wiki markup description to markdown
+pub fn wiki_to_markdown(wiki: &str) -> String {
+    let mut output = Vec::new();
+    let mut in_code_block = false;
+
+    for line in wiki.replace("\r\n", "\n").lines() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("{code") || trimmed.starts_with("{noformat") {
+            let language = if in_code_block {
+                ""
+            } else {
+                trimmed
+                    .trim_start_matches("{code")
+                    .trim_start_matches("{noformat")
+                    .trim_start_matches(':')
+                    .trim_end_matches('}')
+            };
+            output.push(format!("```{}", language));
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            output.push(line.to_string());
+            continue;
+        }
+
+        output.push(wiki_line_to_markdown(trimmed));
+    }
+
+    output.join("\n").trim().to_string()
+}
+
+fn wiki_line_to_markdown(line: &str) -> String {
+    // Headings such as `h2. Description`
+    if let Some(level) = line
+        .strip_prefix('h')
+        .and_then(|rest| rest.chars().next())
+        .and_then(|c| c.to_digit(10))
+    {
+        if let Some(title) = line[2..].strip_prefix(". ") {
+            return format!("{} {}", "#".repeat(level as usize), wiki_inline(title));
+        }
+    }
+
+    // Lists such as `** nested item` or `# numbered item`
+    let marker_len = line.chars().take_while(|c| *c == '*' || *c == '#').count();
+    if marker_len > 0 && line[marker_len..].starts_with(' ') {
+        // Aligned with the text of the parent items, `1. ` being wider than `- `
+        let indentation: String = line[..marker_len - 1]
+            .chars()
+            .map(|c| if c == '#' { "   " } else { "  " })
+            .collect();
+        let marker = if line[..marker_len].ends_with('#') {
+            "1."
+        } else {
+            "-"
+        };
+
+        return format!(
+            "{}{} {}",
+            indentation,
+            marker,
+            wiki_inline(line[marker_len..].trim())
+        );
+    }
+
+    if line == "----" {
+        return "---".to_string();
+    }
+
+    if let Some(quote) = line.strip_prefix("bq. ") {
+        return format!("> {}", wiki_inline(quote));
+    }
+
+    wiki_inline(line)
+}
+
+/// Converts inline wiki markup : `*bold*`, `_italic_`, `{{monospace}}` and `[text|url]` links
+fn wiki_inline(text: &str) -> String {
+    let mut output = String::new();
+    let mut i = 0;
+
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+
+        if let Some(end) = rest.strip_prefix("{{").and_then(|inner| inner.find("}}")) {
+            output.push_str(&format!("`{}`", &rest[2..end + 2]));
+            i += end + 4;
+            continue;
+        }
+
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                match rest[1..end].split_once('|') {
+                    Some((label, url)) => output.push_str(&format!("[{}]({})", label, url)),
+                    None => output.push_str(&format!("<{}>", &rest[1..end])),
+                }
+                i += end + 1;
+                continue;
+            }
+        }
+
+        // Emphasis markers only count at the start of a word, ie: not in snake_case names
+        let word_start = !output.ends_with(|p: char| p.is_alphanumeric());
+        if (c == '*' || c == '_') && word_start {
+            let closing = rest[1..]
+                .find(c)
+                .filter(|end| *end > 0 && !rest[1..end + 1].ends_with(' '));
+
+            if let Some(end) = closing {
+                let wrapper = if c == '*' { "**" } else { "*" };
+                output.push_str(&format!("{}{}{}", wrapper, &rest[1..end + 1], wrapper));
+                i += end + 2;
+                continue;
+            }
+        }
+
+        output.push(c);
+        i += c.len_utf8();
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text(text: &str) -> Value {
+        json!({ "type": "text", "text": text })
+    }
+
+    fn paragraph(content: Vec<Value>) -> Value {
+        json!({ "type": "paragraph", "content": content })
+    }
+
+    fn item(content: Vec<Value>) -> Value {
+        json!({ "type": "listItem", "content": content })
+    }
+
+    fn task(state: &str, content: &str) -> Value {
+        json!({ "type": "taskItem", "attrs": { "state": state }, "content": [text(content)] })
+    }
+
+    #[test]
+    fn adf_headings_paragraphs_and_marks() {
+        let doc = json!({
+            "type": "doc",
+            "content": [
+                {
+                    "type": "heading",
+                    "attrs": { "level": 1 },
+                    "content": [text("User wish")]
+                },
+                paragraph(vec![
+                    json!({ "type": "text", "text": "As the:", "marks": [{ "type": "strong" }] }),
+                    text(" user, see "),
+                    json!({
+                        "type": "text",
+                        "text": "the docs",
+                        "marks": [{ "type": "link", "attrs": { "href": "https://docs.rs" } }]
+                    }),
+                ]),
+                {
+                    "type": "codeBlock",
+                    "attrs": { "language": "rust" },
+                    "content": [text("fn main() {}")]
+                },
+            ]
+        });
+
+        assert_eq!(
+            adf_to_markdown(&doc),
+            "# User wish\n\n\
+             **As the:** user, see [the docs](https://docs.rs)\n\n\
+             ```rust\nfn main() {}\n```"
+        );
+    }
+
+    #[test]
+    fn adf_nested_lists_and_tasks() {
+        let doc = json!({
+            "type": "doc",
+            "content": [
+                {
+                    "type": "orderedList",
+                    "content": [
+                        item(vec![
+                            paragraph(vec![text("first")]),
+                            json!({
+                                "type": "bulletList",
+                                "content": [item(vec![paragraph(vec![text("nested")])])]
+                            }),
+                        ]),
+                        item(vec![paragraph(vec![text("second")])]),
+                    ]
+                },
+                {
+                    "type": "taskList",
+                    "content": [
+                        task("DONE", "done"),
+                        task("TODO", "todo"),
+                    ]
+                },
+            ]
+        });
+
+        assert_eq!(
+            adf_to_markdown(&doc),
+            "1. first\n   - nested\n2. second\n\n- [x] done\n- [ ] todo"
+        );
+    }
+
+    #[test]
+    fn wiki_markup() {
+        let wiki = "h1. User wish\r\n\
+                    *As the:* user\r\n\
+                    * item with {{code}}\r\n\
+                    ** nested [docs|https://docs.rs]\r\n\
+                    # numbered\r\n\
+                    #* nested in numbered\r\n\
+                    {code:rust}\r\n*not bold*\r\n{code}";
+
+        assert_eq!(
+            wiki_to_markdown(wiki),
+            "# User wish\n\
+             **As the:** user\n\
+             - item with `code`\n  \
+             - nested [docs](https://docs.rs)\n\
+             1. numbered\n   \
+             - nested in numbered\n\
+             ```rust\n*not bold*\n```"
+        );
+    }
+
+    #[test]
+    fn wiki_emphasis_ignores_snake_case() {
+        assert_eq!(
+            wiki_inline("a snake_case_name and _italic_"),
+            "a snake_case_name and *italic*"
+        );
+    }
+}
@@ -0,0 +1,463 @@
+mod convert;
+mod model;
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client, ClientBuilder, StatusCode, Url,
+};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+};
+
+use model::{Issue, SearchResponse};
+
+use crate::{
+    cache::CacheMode,
+    config::{JiraConfig, JiraSection},
+    source::{
+        card::{IssueState, ItemType, ProjectCard},
+        CardSource, SourceError, REQUEST_TIMEOUT,
+    },
+};
+
+const CLOUD_SEARCH_ROUTE: &str = "rest/api/3/search/jql";
+const DATA_CENTER_SEARCH_ROUTE: &str = "rest/api/2/search";
+const PAGE_SIZE: usize = 100;
+
+#[derive(Debug)]
+pub enum JiraError {
+    /// The configured jira url is not valid
+    InvalidUrl(String),
+    /// The request could not be sent or the response could not be read
+    Transport(reqwest::Error),
+    /// The API answered with an unexpected status code
+    HttpStatus { status: StatusCode, message: String },
+    /// The response does not have the expected shape
+    Schema(serde_json::Error),
+    /// A recorded response could not be read or written
+    Cache(std::io::Error),
+}
+
+impl Display for JiraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JiraError::InvalidUrl(url) => write!(f, "Invalid jira url : {}", url),
+            JiraError::Transport(e) => write!(f, "Jira request failed : {}", e),
+            JiraError::HttpStatus { status, message } => {
+                write!(f, "Jira answered with status {} : {}", status, message)
+            }
+            JiraError::Schema(e) => {
+                write!(
+                    f,
+                    "Jira response does not match the expected format : {}",
+                    e
+                )
+            }
+            JiraError::Cache(e) => write!(f, "Recorded jira response unavailable : {}", e),
+        }
+    }
+}
+
+impl Error for JiraError {}
+
+/// Card source reading the issues matching a JQL query
+///
+/// Jira cloud is used when an email is configured, authenticating with the email and an API
+/// token. Otherwise the data center API is used with a personal access token.
+pub struct JiraClient {
+    client: Client,
+    conf: JiraConfig,
+    cache: CacheMode,
+}
+
+/// Position in the search results, cloud and data center paginate differently
+enum Page {
+    Cloud(Option<String>),
+    DataCenter(usize),
+}
+
+impl JiraClient {
+    pub fn new(conf: &JiraConfig, cache: CacheMode) -> JiraClient {
+        let mut headers = HeaderMap::with_capacity(2);
+        headers.insert("Accept", HeaderValue::from_static("application/json"));
+
+        // Jira cloud uses basic authentication, added on each request
+        if conf.email.is_none() {
+            headers.insert(
+                "Authorization",
+                HeaderValue::from_str(&format!("Bearer {}", conf.token)).unwrap(),
+            );
+        }
+
+        JiraClient {
+            client: ClientBuilder::new()
+                .default_headers(headers)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap(),
+            conf: conf.clone(),
+            cache,
+        }
+    }
+
+    fn is_cloud(&self) -> bool {
+        self.conf.email.is_some()
+    }
+
+    /// Fields requested for every issue
+    fn requested_fields(&self) -> String {
+        let mut fields = vec![
+            "summary",
+            "description",
+            "status",
+            "components",
+            "assignee",
+            "parent",
+            "timeoriginalestimate",
+            "labels",
+        ];
+        fields.extend(self.conf.sub_section_field.as_deref());
+        fields.extend(self.conf.story_points_field.as_deref());
+
+        fields.join(",")
+    }
+
+    fn search_url(&self, page: &Page) -> Result<Url, JiraError> {
+        let route = if self.is_cloud() {
+            CLOUD_SEARCH_ROUTE
+        } else {
+            DATA_CENTER_SEARCH_ROUTE
+        };
+
+        let base = format!("{}/", self.conf.url.trim_end_matches('/'));
+        let mut url = Url::parse(&base)
+            .and_then(|base| base.join(route))
+            .map_err(|_| JiraError::InvalidUrl(self.conf.url.clone()))?;
+
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("jql", &self.conf.jql)
+                .append_pair("fields", &self.requested_fields())
+                .append_pair("maxResults", &PAGE_SIZE.to_string());
+
+            match page {
+                Page::Cloud(Some(token)) => {
+                    query.append_pair("nextPageToken", token);
+                }
+                Page::Cloud(None) => (),
+                Page::DataCenter(start_at) => {
+                    query.append_pair("startAt", &start_at.to_string());
+                }
+            }
+        }
+
+        Ok(url)
+    }
+
+    /// Fetches one page of search results
+    ///
+    /// `name` identifies the response when recording or replaying
+    async fn search(&self, name: &str, page: &Page) -> Result<SearchResponse, JiraError> {
+        let body = match self.cache.replay(name).map_err(JiraError::Cache)? {
+            Some(recorded) => recorded,
+            None => {
+                let mut request = self.client.get(self.search_url(page)?);
+                if let Some(email) = &self.conf.email {
+                    request = request.basic_auth(email, Some(&self.conf.token));
+                }
+
+                let resp = request.send().await.map_err(JiraError::Transport)?;
+                let status = resp.status();
+                let body = resp.bytes().await.map_err(JiraError::Transport)?;
+
+                if status != StatusCode::OK {
+                    return Err(JiraError::HttpStatus {
+                        status,
+                        message: String::from_utf8_lossy(&body).to_string(),
+                    });
+                }
+
+                self.cache.record(name, &body).map_err(JiraError::Cache)?;
+
+                body.to_vec()
+            }
+        };
+
+        serde_json::from_slice(&body).map_err(JiraError::Schema)
+    }
+
+    /// Fetches every issue matching the JQL query, following the pagination
+    pub async fn get_issues(&self) -> Result<Vec<Issue>, JiraError> {
+        let mut issues = Vec::new();
+        let mut page = if self.is_cloud() {
+            Page::Cloud(None)
+        } else {
+            Page::DataCenter(0)
+        };
+
+        for page_index in 0.. {
+            let resp = self
+                .search(&format!("jira_search_{}.json", page_index), &page)
+                .await?;
+            let fetched = resp.issues.len();
+            issues.extend(resp.issues);
+
+            page = match page {
+                Page::Cloud(_) => match resp.next_page_token {
+                    Some(token) if !resp.is_last.unwrap_or(false) => Page::Cloud(Some(token)),
+                    _ => break,
+                },
+                Page::DataCenter(_) => {
+                    let next = resp.start_at.unwrap_or_default() + fetched;
+                    if fetched == 0 || next >= resp.total.unwrap_or_default() {
+                        break;
+                    }
+                    Page::DataCenter(next)
+                }
+            };
+        }
+
+        Ok(issues)
+    }
+
+    fn to_card(&self, issue: Issue) -> ProjectCard {
+        let fields = issue.fields;
+
+        let section = match self.conf.section {
+            JiraSection::Epic => fields.parent.map(|p| p.fields.summary),
+            JiraSection::Component => fields.components.into_iter().next().map(|c| c.name),
+        };
+        let sub_section = self
+            .conf
+            .sub_section_field
+            .as_ref()
+            .and_then(|id| fields.custom.get(id))
+            .and_then(custom_field_text);
+
+        let status = match fields.status.status_category.key.as_str() {
+            "done" => "Done",
+            "indeterminate" => "In Progress",
+            _ => "Todo",
+        };
+
+        // Story points take precedence over the original estimate
+        let story_points = self
+            .conf
+            .story_points_field
+            .as_ref()
+            .and_then(|id| fields.custom.get(id))
+            .and_then(|v| v.as_f64());
+        let working_days = match (story_points, fields.timeoriginalestimate) {
+            (Some(points), _) => points as f32,
+            (None, Some(seconds)) => seconds as f32 / (self.conf.hours_per_day * 3600.0),
+            (None, None) => 0.0,
+        };
+
+        let content = match fields.description {
+            Some(serde_json::Value::String(wiki)) => convert::wiki_to_markdown(&wiki),
+            Some(doc @ serde_json::Value::Object(_)) => convert::adf_to_markdown(&doc),
+            _ => String::new(),
+        };
+
+        ProjectCard {
+            name: fields.summary,
+            content,
+            section: section.unwrap_or_default(),
+            sub_section: sub_section.unwrap_or_default(),
+            working_days,
+            status: status.to_string(),
+            assignees: fields
+                .assignee
+                .into_iter()
+                .map(|a| a.display_name)
+                .collect(),
+            assignee_names: BTreeMap::new(),
+            fields: BTreeMap::new(),
+            labels: fields.labels,
+            state: Some(match fields.status.status_category.key.as_str() {
+                "done" => IssueState::Closed,
+                _ => IssueState::Open,
+            }),
+            item_type: ItemType::Issue,
+            archived: false,
+            url: Some(format!(
+                "{}/browse/{}",
+                self.conf.url.trim_end_matches('/'),
+                issue.key
+            )),
+            id: None,
+            references: vec![issue.key],
+            repository: None,
+            dependencies: Vec::new(),
+            linked_issues: Vec::new(),
+        }
+    }
+}
+
+/// Text of a custom field, which may be a plain string, a select option or a list of those
+fn custom_field_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Object(option) => option
+            .get("value")
+            .or_else(|| option.get("name"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        serde_json::Value::Array(values) => values.first().and_then(custom_field_text),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl CardSource for JiraClient {
+    fn name(&self) -> String {
+        format!("jira query \"{}\"", self.conf.jql)
+    }
+
+    async fn get_cards(&self) -> Result<Vec<ProjectCard>, SourceError> {
+        let issues = self.get_issues().await.map_err(SourceError::Jira)?;
+
+        Ok(issues.into_iter().map(|i| self.to_card(i)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{basic_auth, bearer_token, method, path, query_param, query_param_is_missing},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn client(server: &MockServer, email: Option<&str>) -> JiraClient {
+        let mut conf: JiraConfig = toml::from_str(&format!(
+            "url = \"{}/\"\ntoken = \"secret\"\njql = \"project = PLD\"\n\
+             story_points_field = \"customfield_10016\"",
+            server.uri()
+        ))
+        .unwrap();
+        conf.email = email.map(str::to_string);
+
+        JiraClient::new(&conf, CacheMode::Live)
+    }
+
+    fn issue(key: &str, category: &str) -> serde_json::Value {
+        json!({
+            "key": key,
+            "fields": {
+                "summary": format!("Issue {}", key),
+                "description": null,
+                "status": { "statusCategory": { "key": category } },
+                "components": [],
+                "assignee": { "displayName": "Léon Sautour" },
+                "parent": { "fields": { "summary": "Backend" } },
+                "labels": [],
+                "timeoriginalestimate": 4 * 3600,
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn cloud_search_follows_the_page_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rest/api/3/search/jql"))
+            .and(basic_auth("me@team.com", "secret"))
+            .and(query_param("jql", "project = PLD"))
+            .and(query_param_is_missing("nextPageToken"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issues": [issue("PLD-1", "done")],
+                "nextPageToken": "page-2",
+                "isLast": false,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/rest/api/3/search/jql"))
+            .and(query_param("nextPageToken", "page-2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issues": [issue("PLD-2", "new")],
+                "isLast": true,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cards = client(&server, Some("me@team.com"))
+            .get_cards()
+            .await
+            .unwrap();
+
+        let keys: Vec<&str> = cards.iter().map(|c| c.references[0].as_str()).collect();
+        assert_eq!(keys, ["PLD-1", "PLD-2"]);
+    }
+
+    #[tokio::test]
+    async fn data_center_search_follows_start_at() {
+        let server = MockServer::start().await;
+        for (start_at, issues) in [(0, vec!["PLD-1", "PLD-2"]), (2, vec!["PLD-3"])] {
+            Mock::given(method("GET"))
+                .and(path("/rest/api/2/search"))
+                .and(bearer_token("secret"))
+                .and(query_param("startAt", start_at.to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "issues": issues.iter().map(|key| issue(key, "new")).collect::<Vec<_>>(),
+                    "startAt": start_at,
+                    "total": 3,
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let cards = client(&server, None).get_cards().await.unwrap();
+
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[2].name, "Issue PLD-3");
+    }
+
+    #[tokio::test]
+    async fn issues_are_mapped_to_cards() {
+        let server = MockServer::start().await;
+        let mut story = issue("PLD-2", "indeterminate");
+        story["fields"]["customfield_10016"] = json!(3);
+        story["fields"]["description"] = json!({
+            "type": "doc",
+            "content": [{
+                "type": "paragraph",
+                "content": [{ "type": "text", "text": "Body" }]
+            }]
+        });
+
+        Mock::given(path("/rest/api/2/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issues": [issue("PLD-1", "done"), story],
+                "startAt": 0,
+                "total": 2,
+            })))
+            .mount(&server)
+            .await;
+
+        let cards = client(&server, None).get_cards().await.unwrap();
+
+        // The epic gives the section and the original estimate the working days
+        assert_eq!(cards[0].section, "Backend");
+        assert_eq!(cards[0].status, "Done");
+        assert_eq!(cards[0].working_days, 0.5);
+        assert_eq!(cards[0].assignees, ["Léon Sautour"]);
+        assert_eq!(
+            cards[0].url.as_deref(),
+            Some(format!("{}/browse/PLD-1", server.uri()).as_str())
+        );
+        // Story points take precedence over the estimate
+        assert_eq!(cards[1].status, "In Progress");
+        assert_eq!(cards[1].working_days, 3.0);
+        assert_eq!(cards[1].content, "Body");
+    }
+}
@@ -0,0 +1,74 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+pub struct Named {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct StatusCategory {
+    /// One of `new`, `indeterminate` or `done`
+    pub key: String,
+}
+
+#[derive(Deserialize)]
+pub struct Status {
+    #[serde(rename = "statusCategory")]
+    pub status_category: StatusCategory,
+}
+
+#[derive(Deserialize)]
+pub struct Assignee {
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
+
+#[derive(Deserialize)]
+pub struct ParentFields {
+    pub summary: String,
+}
+
+/// Parent issue, which is the epic on team managed and recent company managed projects
+#[derive(Deserialize)]
+pub struct Parent {
+    pub fields: ParentFields,
+}
+
+#[derive(Deserialize)]
+pub struct IssueFields {
+    pub summary: String,
+    /// Atlassian document format on cloud, wiki markup string on data center
+    pub description: Option<serde_json::Value>,
+    pub status: Status,
+    #[serde(default)]
+    pub components: Vec<Named>,
+    pub assignee: Option<Assignee>,
+    pub parent: Option<Parent>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Original estimate in seconds
+    pub timeoriginalestimate: Option<u64>,
+    /// Custom fields, indexed by their id such as `customfield_10016`
+    #[serde(flatten)]
+    pub custom: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct Issue {
+    pub key: String,
+    pub fields: IssueFields,
+}
+
+/// Search response, cloud paginates through `nextPageToken` while data center uses `startAt`
+#[derive(Deserialize)]
+pub struct SearchResponse {
+    pub issues: Vec<Issue>,
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+    #[serde(rename = "isLast")]
+    pub is_last: Option<bool>,
+    #[serde(rename = "startAt")]
+    pub start_at: Option<usize>,
+    pub total: Option<usize>,
+}
@@ -0,0 +1,409 @@
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    app::{card_filter, GeneratorError},
+    cache::CacheMode,
+    config::{CardSchema, Config},
+    graph::DependencyGraph,
+    parsing::{ParsingError, PldCard},
+    source::{self, card::ProjectCard, filter::Filter, CardSource, MergedCards},
+};
+
+/// Output format of the lint report
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+pub enum LintFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// One problem found on a card
+#[derive(Serialize)]
+pub struct LintIssue {
+    /// Stable identifier of the check, ie: `missing_status` or `section_missing`
+    pub kind: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl LintIssue {
+    fn new(kind: &'static str, message: String) -> LintIssue {
+        LintIssue {
+            kind,
+            message,
+            suggestion: None,
+            line: None,
+            column: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CardLint {
+    pub title: String,
+    pub url: Option<String>,
+    pub issues: Vec<LintIssue>,
+}
+
+#[derive(Serialize)]
+pub struct LintReport {
+    /// Number of cards checked
+    pub cards: usize,
+    /// Number of issues across every card
+    pub issues: usize,
+    /// Cards with at least one issue
+    pub results: Vec<CardLint>,
+}
+
+/// Checks every card of the configured sources without generating the PLD
+pub struct Linter {
+    card_sources: Vec<Box<dyn CardSource>>,
+    card_schema: CardSchema,
+    filter: Option<Filter>,
+}
+
+impl Linter {
+    pub fn new(
+        conf: &Config,
+        cache: CacheMode,
+        filter: Option<String>,
+    ) -> Result<Linter, GeneratorError> {
+        Ok(Linter {
+            card_sources: source::from_config(conf, cache).map_err(GeneratorError::Source)?,
+            card_schema: conf.schema.clone().unwrap_or_default(),
+            filter: card_filter(conf, filter)?,
+        })
+    }
+
+    /// Fetches and checks every card, cards excluded by the filter are not checked
+    pub async fn run(&self) -> Result<LintReport, GeneratorError> {
+        for source in &self.card_sources {
+            source.validate().await.map_err(GeneratorError::Source)?;
+        }
+
+        let merged = source::merge_cards(&self.card_sources)
+            .await
+            .map_err(GeneratorError::Source)?;
+
+        Ok(self.check(&merged))
+    }
+
+    /// Checks the merged cards of every source
+    fn check(&self, merged: &MergedCards) -> LintReport {
+        let kept = |card: &ProjectCard| self.filter.as_ref().is_none_or(|f| f.matches(card));
+        let cards: Vec<&ProjectCard> = merged.cards.iter().filter(|card| kept(card)).collect();
+        let duplicates = merged.duplicates(kept);
+
+        let parsed: Vec<Result<PldCard, Vec<ParsingError>>> = cards
+            .iter()
+            .map(|card| PldCard::new(card, &self.card_schema))
+            .collect();
+        let mut issues: Vec<Vec<LintIssue>> = cards
+            .iter()
+            .zip(&parsed)
+            .map(|(card, parsed)| self.check_card(card, parsed))
+            .collect();
+
+        // Dependencies are only known for the cards which could be parsed
+        let (indexes, parsed): (Vec<usize>, Vec<PldCard>) = parsed
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, parsed)| Some((i, parsed.ok()?)))
+            .unzip();
+        let graph = DependencyGraph::new(&parsed);
+
+        for (card, reference) in &graph.dangling {
+            issues[indexes[*card]].push(LintIssue::new(
+                "dangling_dependency",
+                format!("Dependency \"{}\" matches no card", reference),
+            ));
+        }
+        for cycle in &graph.cycles {
+            let titles: Vec<String> = cycle
+                .iter()
+                .map(|&card| format!("\"{}\"", parsed[card].name.trim()))
+                .collect();
+
+            for &card in &cycle[1..] {
+                issues[indexes[card]].push(LintIssue::new(
+                    "dependency_cycle",
+                    format!(
+                        "Card is part of a dependency cycle : {}",
+                        titles.join(" -> ")
+                    ),
+                ));
+            }
+        }
+
+        let results: Vec<CardLint> = cards
+            .iter()
+            .zip(issues)
+            .map(|(card, mut issues)| {
+                if let Some(sources) = duplicates.get(card.name.trim()) {
+                    issues.push(LintIssue::new(
+                        "duplicate_title",
+                        format!(
+                            "Title is used in {} projects : {}",
+                            sources.len(),
+                            sources.join(", ")
+                        ),
+                    ));
+                }
+
+                CardLint {
+                    title: card.name.trim().to_string(),
+                    url: card.url.clone(),
+                    issues,
+                }
+            })
+            .filter(|card| !card.issues.is_empty())
+            .collect();
+
+        LintReport {
+            cards: cards.len(),
+            issues: results.iter().map(|card| card.issues.len()).sum(),
+            results,
+        }
+    }
+
+    fn check_card(
+        &self,
+        card: &ProjectCard,
+        parsed: &Result<PldCard, Vec<ParsingError>>,
+    ) -> Vec<LintIssue> {
+        let mut issues: Vec<LintIssue> = card
+            .missing_fields()
+            .into_iter()
+            .map(|field| {
+                LintIssue::new(
+                    match field {
+                        "Section" => "missing_section",
+                        "Sub-section" => "missing_sub_section",
+                        "Status" => "missing_status",
+                        "Working days" => "missing_working_days",
+                        _ => "missing_field",
+                    },
+                    format!("Card has no {}", field),
+                )
+            })
+            .collect();
+
+        if card.assignees.is_empty() {
+            issues.push(LintIssue::new(
+                "no_assignees",
+                "Card has no assignees".to_string(),
+            ));
+        }
+
+        match parsed {
+            Ok(parsed) => {
+                if parsed.dod_items.is_empty() {
+                    issues.push(LintIssue::new(
+                        "empty_dod",
+                        "Definition of done has no list item".to_string(),
+                    ));
+                }
+            }
+            Err(errors) => issues.extend(errors.iter().map(|error| {
+                let location = error.location();
+
+                LintIssue {
+                    kind: error.kind(),
+                    message: error.to_string(),
+                    suggestion: Some(error.suggestion(&self.card_schema)),
+                    line: location.map(|l| l.line),
+                    column: location.map(|l| l.column),
+                }
+            })),
+        }
+
+        issues
+    }
+}
+
+impl LintReport {
+    /// Exit code of the lint command, non-zero when any issue was found
+    pub fn exit_code(&self) -> i32 {
+        if self.issues > 0 {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn print(&self, format: LintFormat) {
+        match format {
+            LintFormat::Json => println!("{}", self.to_json()),
+            LintFormat::Text => {
+                for card in &self.results {
+                    match &card.url {
+                        Some(url) => println!("\"{}\" ({})", card.title.blue(), url),
+                        None => println!("\"{}\"", card.title.blue()),
+                    }
+
+                    for issue in &card.issues {
+                        println!("  - [{}] {}", issue.kind, issue.message.red());
+                        if let Some(suggestion) = &issue.suggestion {
+                            println!("    {}", suggestion);
+                        }
+                    }
+                    println!();
+                }
+
+                println!(
+                    "{} issue(s) found in {} of {} card(s)",
+                    self.issues,
+                    self.results.len(),
+                    self.cards
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::card::fixtures::{self, BODY};
+
+    fn linter() -> Linter {
+        Linter {
+            card_sources: Vec::new(),
+            card_schema: CardSchema::default(),
+            filter: None,
+        }
+    }
+
+    /// Card with every information filled, raising no issue
+    fn card(name: &str, content: &str) -> ProjectCard {
+        ProjectCard {
+            section: "Backend".to_string(),
+            sub_section: "Accounts".to_string(),
+            status: "Todo".to_string(),
+            working_days: 1.0,
+            assignees: vec!["jdoe".to_string()],
+            ..fixtures::card(name, content)
+        }
+    }
+
+    fn merged(cards: Vec<(&str, ProjectCard)>) -> MergedCards {
+        MergedCards {
+            sources: cards.iter().map(|(source, _)| source.to_string()).collect(),
+            cards: cards.into_iter().map(|(_, card)| card).collect(),
+        }
+    }
+
+    fn kinds(report: &LintReport, title: &str) -> Vec<&'static str> {
+        report
+            .results
+            .iter()
+            .find(|card| card.title == title)
+            .map(|card| card.issues.iter().map(|issue| issue.kind).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn complete_cards_pass() {
+        let report = linter().check(&merged(vec![("front", card("Login", BODY))]));
+
+        assert_eq!(report.cards, 1);
+        assert_eq!(report.issues, 0);
+        assert!(report.results.is_empty());
+        assert_eq!(report.exit_code(), 0);
+    }
+
+    #[test]
+    fn missing_information_is_reported() {
+        let mut incomplete = card("Login", BODY);
+        incomplete.section.clear();
+        incomplete.working_days = 0.0;
+        incomplete.assignees.clear();
+
+        let report = linter().check(&merged(vec![("front", incomplete)]));
+
+        assert_eq!(
+            kinds(&report, "Login"),
+            ["missing_section", "missing_working_days", "no_assignees"]
+        );
+        assert_eq!(report.issues, 3);
+        assert_eq!(report.exit_code(), 1);
+    }
+
+    #[test]
+    fn parsing_errors_and_empty_dod_are_reported() {
+        let report = linter().check(&merged(vec![
+            (
+                "front",
+                card("Login", &BODY.replace("- done", "Nothing yet")),
+            ),
+            (
+                "front",
+                card("Signup", &BODY.replace("# DOD\n\n- done", "")),
+            ),
+        ]));
+
+        assert_eq!(kinds(&report, "Login"), ["empty_dod"]);
+        assert_eq!(kinds(&report, "Signup"), ["section_missing"]);
+        assert_eq!(report.exit_code(), 1);
+    }
+
+    #[test]
+    fn titles_shared_across_projects_are_reported() {
+        let report = linter().check(&merged(vec![
+            ("front", card("Login", BODY)),
+            ("back", card("Login", BODY)),
+            ("back", card("Signup", BODY)),
+            ("back", card("Signup", BODY)),
+        ]));
+
+        assert_eq!(report.results.len(), 2);
+        assert!(report
+            .results
+            .iter()
+            .all(|card| card.title == "Login" && card.issues[0].kind == "duplicate_title"));
+    }
+
+    #[test]
+    fn json_report() {
+        let mut card = card("Login", &BODY.replace("# DOD\n\n- done", ""));
+        card.url = Some("https://example.com/1".to_string());
+        card.status.clear();
+
+        let report = linter().check(&merged(vec![("front", card)]));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "cards": 1,
+                "issues": 2,
+                "results": [{
+                    "title": "Login",
+                    "url": "https://example.com/1",
+                    "issues": [
+                        {
+                            "kind": "missing_status",
+                            "message": "Card has no Status",
+                        },
+                        {
+                            "kind": "section_missing",
+                            "message": "Section \"DOD\" is missing",
+                            "suggestion": "Add a \"# DOD\" heading as shown in card_format.md",
+                        },
+                    ],
+                }],
+            })
+        );
+    }
+}
@@ -2,15 +2,17 @@ mod model;
 
 use reqwest::{
     header::{HeaderMap, HeaderValue},
-    Client, ClientBuilder, StatusCode, Url,
+    Client, ClientBuilder, StatusCode,
 };
 use std::{
-    fs::File,
-    io::{copy, Cursor},
+    error::Error,
+    fmt::{self, Display},
 };
 
 use model::{GetDocumentResponse, IntrospectBody, IntrospectResponse};
 
+use crate::cache::CacheMode;
+
 use self::model::{RefreshBody, RefreshResponse};
 
 const API_VERSION: &str = "1";
@@ -18,6 +20,7 @@ const INTROSPECT_TOKEN_ROUTE: &str = "https://api.lucid.co/oauth2/token/introspe
 const REFRESH_TOKEN_ROUTE: &str = "https://api.lucid.co/oauth2/token";
 const EXPORT_DOCUMENT_ROUTE: &str = "https://api.lucid.co/documents/";
 const GET_DOCUMENT_ROUTE: &str = "https://api.lucid.co/documents/";
+const DOCUMENT_RECORDING: &str = "lucid_document.json";
 
 pub struct OauthId {
     client_id: String,
@@ -30,19 +33,41 @@ pub struct LucidClient {
     pub access_token: String,
     pub refresh_token: String,
     oauth_id: OauthId,
+    cache: CacheMode,
 }
 
 #[derive(Debug)]
 pub enum LucidError {
+    /// The request could not be sent or the response could not be read
+    Transport(reqwest::Error),
     ExpiredToken,
     UnexpectedResponse,
+    CacheError(std::io::Error),
+    /// The exported image could not be written to the output directory
+    Image(std::io::Error),
 }
 
+impl Display for LucidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LucidError::Transport(e) => write!(f, "Lucid request failed : {}", e),
+            LucidError::ExpiredToken => write!(f, "The lucid token has expired"),
+            LucidError::UnexpectedResponse => {
+                write!(f, "Lucid answered with an unexpected response")
+            }
+            LucidError::CacheError(e) => write!(f, "Recorded lucid response unavailable : {}", e),
+            LucidError::Image(e) => write!(f, "Writing the lucid image has failed : {}", e),
+        }
+    }
+}
+
+impl Error for LucidError {}
+
 impl LucidClient {
     /// Introspects given token, returns true if token is still valid
     ///
     /// Note : Has weird behavious with refresh_token so use it exclusively for access_token
-    pub async fn check_access_token(&self, token: &str) -> bool {
+    pub async fn check_access_token(&self, token: &str) -> Result<bool, LucidError> {
         let body = IntrospectBody {
             client_id: self.oauth_id.client_id.clone(),
             client_secret: self.oauth_id.client_secret.clone(),
@@ -55,14 +80,14 @@ impl LucidClient {
             .json(&body)
             .send()
             .await
-            .expect("Error sending access token introspection request")
+            .map_err(LucidError::Transport)?
             .json()
             .await
-            .expect("Error deserializing access token introspection response");
+            .map_err(|_| LucidError::UnexpectedResponse)?;
 
         match res {
-            IntrospectResponse::Success(res) => res.active,
-            IntrospectResponse::Error(_) => false,
+            IntrospectResponse::Success(res) => Ok(res.active),
+            IntrospectResponse::Error(_) => Ok(false),
         }
     }
 
@@ -81,7 +106,7 @@ impl LucidClient {
             .json(&body)
             .send()
             .await
-            .expect("Error sending access token introspection request");
+            .map_err(LucidError::Transport)?;
 
         match res.status() {
             StatusCode::UNAUTHORIZED => return Err(LucidError::ExpiredToken),
@@ -89,10 +114,8 @@ impl LucidClient {
             _ => return Err(LucidError::UnexpectedResponse),
         };
 
-        let res: RefreshResponse = res.json().await.expect(
-            "Error deserializing refresh token response.
-                This most likely means that the refresh token used is invalid or has expired",
-        );
+        // An unreadable response most likely means that the refresh token is invalid
+        let res: RefreshResponse = res.json().await.map_err(|_| LucidError::ExpiredToken)?;
 
         self.access_token = res.access_token;
         self.refresh_token = res.refresh_token;
@@ -107,26 +130,35 @@ impl LucidClient {
         document_id: &str,
         page: u8,
     ) -> Result<(), LucidError> {
-        let page_str = page.to_string();
-        let params = Vec::from([("page", page_str.as_ref()), ("crop", "content")]);
+        let recording = format!("lucid_page_{}.png", page);
 
-        let mut query_string = Url::parse_with_params(EXPORT_DOCUMENT_ROUTE, params).unwrap();
-        query_string.set_path(&format!("/documents/{}", document_id));
+        if let Some(image) = self
+            .cache
+            .replay(&recording)
+            .map_err(LucidError::CacheError)?
+        {
+            std::fs::write(destination, image).map_err(LucidError::Image)?;
+            return Ok(());
+        }
 
         let resp = self
             .client
-            .get(query_string)
+            .get(format!("{}{}", EXPORT_DOCUMENT_ROUTE, document_id))
+            .query(&[("page", page.to_string().as_str()), ("crop", "content")])
             .header("Accept", "image/png")
             .header("Authorization", &format!("Bearer {}", self.access_token))
             .send()
             .await
-            .unwrap();
+            .map_err(LucidError::Transport)?;
 
         match resp.status() {
             StatusCode::OK => {
-                let mut f = File::create(destination).unwrap();
-                let mut writer = Cursor::new(resp.bytes().await.unwrap());
-                copy(&mut writer, &mut f).expect("Error copying image to file");
+                let image = resp.bytes().await.map_err(LucidError::Transport)?;
+                self.cache
+                    .record(&recording, &image)
+                    .map_err(LucidError::CacheError)?;
+
+                std::fs::write(destination, image).map_err(LucidError::Image)?;
 
                 Ok(())
             }
@@ -136,6 +168,17 @@ impl LucidClient {
     }
 
     pub async fn get_page_count(&self, document_id: &str) -> Result<u8, LucidError> {
+        if let Some(recorded) = self
+            .cache
+            .replay(DOCUMENT_RECORDING)
+            .map_err(LucidError::CacheError)?
+        {
+            let body: GetDocumentResponse =
+                serde_json::from_slice(&recorded).map_err(|_| LucidError::UnexpectedResponse)?;
+
+            return Ok(body.page_count);
+        }
+
         let query_str = format!("{}{}", GET_DOCUMENT_ROUTE, document_id);
 
         let resp = self
@@ -146,11 +189,17 @@ impl LucidClient {
             .header("Lucid-Api-Version", "1")
             .send()
             .await
-            .unwrap();
+            .map_err(LucidError::Transport)?;
 
         match resp.status() {
             StatusCode::OK => {
-                let body: GetDocumentResponse = resp.json().await.expect("Deserialization failed");
+                let raw_body = resp.bytes().await.map_err(LucidError::Transport)?;
+                self.cache
+                    .record(DOCUMENT_RECORDING, &raw_body)
+                    .map_err(LucidError::CacheError)?;
+
+                let body: GetDocumentResponse = serde_json::from_slice(&raw_body)
+                    .map_err(|_| LucidError::UnexpectedResponse)?;
 
                 Ok(body.page_count)
             }
@@ -164,6 +213,7 @@ impl LucidClient {
         refresh_token: &str,
         client_id: &str,
         client_secret: &str,
+        cache: CacheMode,
     ) -> LucidClient {
         let mut headers = HeaderMap::with_capacity(1);
         headers.insert("Lucid-Api-Version", HeaderValue::from_static(API_VERSION));
@@ -179,6 +229,37 @@ impl LucidClient {
                 client_id: client_id.to_string(),
                 client_secret: client_secret.to_string(),
             },
+            cache,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn replaying_client() -> LucidClient {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/lucid");
+
+        LucidClient::new("access", "refresh", "id", "secret", CacheMode::Replay(dir))
+    }
+
+    #[tokio::test]
+    async fn corrupt_recordings_are_reported() {
+        let error = replaying_client().get_page_count("document").await;
+
+        assert!(matches!(error, Err(LucidError::UnexpectedResponse)));
+    }
+
+    #[tokio::test]
+    async fn missing_recordings_are_reported() {
+        let error = replaying_client()
+            .export_image("unused.png", "document", 1)
+            .await;
+
+        assert!(
+            matches!(error, Err(LucidError::CacheError(ref e)) if e.to_string().contains("lucid_page_1.png"))
+        );
+    }
+}
@@ -8,6 +8,7 @@ pub struct IntrospectBody {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 pub struct IntrospectOk {
     pub active: bool,
     pub user_id: i64,
@@ -19,12 +20,14 @@ pub struct IntrospectOk {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 pub struct IntrospectErr {
     pub active: bool,
 }
 
 #[derive(Deserialize)]
 #[serde(untagged)]
+#[allow(dead_code)]
 pub enum IntrospectResponse {
     Success(IntrospectOk),
     Error(IntrospectErr),
@@ -40,6 +43,7 @@ pub struct RefreshBody {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 pub struct RefreshResponse {
     pub access_token: String,
     pub user_id: i64,
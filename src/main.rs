@@ -1,50 +1,148 @@
 mod app;
+mod cache;
 mod config;
+mod fix;
 mod github;
+mod gitlab;
+mod graph;
 mod image_cropping;
+mod jira;
+mod lint;
 mod lucid;
+mod numbering;
 mod parsing;
+mod source;
 
 use app::App;
-use clap::Parser;
+use cache::CacheMode;
+use clap::{Parser, Subcommand};
+use colored::Colorize;
 use config::Config;
-use std::{error::Error, fs::File, io::Read};
-use tokio;
+use fix::Fixer;
+use lint::{LintFormat, Linter};
+use std::{error::Error, fs::File, io::Read, path::PathBuf};
 
 #[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true)]
 /// A simple epitech project log document generator
 struct Args {
     /// Output directory path. Will be created if does not exist already.
-    #[arg(short, long)]
-    pub output: String,
+    #[arg(short, long, required = true)]
+    pub output: Option<String>,
     /// Alternative config file path, if unset will default to ./generator_config.toml
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub conf: Option<String>,
+    /// Save every github and lucid response to the given directory
+    #[arg(long, conflicts_with = "replay", global = true)]
+    pub record: Option<PathBuf>,
+    /// Generate the PLD from responses previously saved with --record, without network access
+    #[arg(long, global = true)]
+    pub replay: Option<PathBuf>,
+    /// Only write the cards matching this expression, overrides the filter of the config
+    #[arg(short, long, global = true)]
+    pub filter: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
 }
 
-fn parse_config(path: &str) -> Option<Config> {
-    let mut file = File::open(path).ok()?;
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check every card against the card format without generating the PLD, exits with a non
+    /// zero status if any issue is found
+    Lint {
+        /// Report format, json is meant for CI
+        #[arg(long, value_enum, default_value_t)]
+        format: LintFormat,
+    },
+    /// Rewrite the card bodies in the layout of card_format.md, only shows the changes unless
+    /// --yes is given
+    Fix {
+        /// Push the rewritten bodies to the card sources
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+fn parse_config(path: &str) -> Result<Config, String> {
     let mut file_content = String::new();
-    file.read_to_string(&mut file_content).ok()?;
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut file_content))
+        .map_err(|e| format!("Cannot read the configuration file {} : {}", path, e))?;
 
-    let mut config: Config = toml::from_str(&file_content).unwrap();
+    // Missing or mistyped settings are reported with their line, ie: missing field `owner`
+    let mut config: Config = toml::from_str(&file_content)
+        .map_err(|e| format!("Invalid configuration file {} : {}", path, e))?;
     config.path = path.to_string();
 
-    Some(config)
+    Ok(config)
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
-    let conf = match args.conf {
-        Some(path) => parse_config(&path),
-        None => parse_config("./generator_config.toml"),
+    let conf = match parse_config(args.conf.as_deref().unwrap_or("./generator_config.toml")) {
+        Ok(conf) => conf,
+        Err(e) => {
+            eprintln!("{} {}", "ERROR:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    // Clap does not check conflicts between subcommand and global arguments
+    if matches!(args.command, Some(Command::Fix { yes: true })) && args.replay.is_some() {
+        return Err(
+            "--yes cannot be used with --replay, the changes would never reach the sources".into(),
+        );
+    }
+
+    let cache = match (args.record, args.replay) {
+        (Some(dir), _) => CacheMode::Record(dir),
+        (_, Some(dir)) => CacheMode::Replay(dir),
+        _ => CacheMode::Live,
+    };
+
+    if let Some(Command::Fix { yes }) = args.command {
+        match Fixer::new(&conf, cache, args.filter)?.run(yes).await {
+            Ok(0) => return Ok(()),
+            Ok(_) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("{} {}", "ERROR:".red(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(Command::Lint { format }) = args.command {
+        let report = match Linter::new(&conf, cache, args.filter)?.run().await {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("{} {}", "ERROR:".red(), e);
+                std::process::exit(1);
+            }
+        };
+
+        report.print(format);
+        if report.exit_code() != 0 {
+            std::process::exit(report.exit_code());
+        }
+
+        return Ok(());
     }
-    .ok_or("Configuration parsing failed")?;
 
-    let mut app = App::new(conf, &args.output)?;
-    app.run().await?;
+    // Required by clap when no subcommand is given
+    let output = args.output.unwrap();
+
+    let mut app = App::new(conf, &output, cache, args.filter)?;
+    let result = app.run().await;
+
+    // Dropping the app saves the config, make sure it happens before exiting
+    drop(app);
+
+    if let Err(e) = result {
+        eprintln!("{} {}", "ERROR:".red(), e);
+        std::process::exit(1);
+    }
 
     Ok(())
 }
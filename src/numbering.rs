@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use crate::parsing::PldCard;
+
+/// Numbers of the sections, sub-sections and cards of the PLD, ie: `2.3.1` for the first card of
+/// the third sub-section of the second section
+///
+/// Anything already numbered keeps its number, new sections, sub-sections and cards are numbered
+/// after the last one of their parent. Numbers of removed cards are never given again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Numbering {
+    sections: BTreeMap<String, usize>,
+    /// Sub-section numbers indexed by section then sub-section
+    sub_sections: BTreeMap<String, BTreeMap<String, usize>>,
+    /// Card numbers indexed by card key, see [`PldCard::key`]
+    cards: BTreeMap<String, String>,
+    /// Last card number given in each sub-section, indexed by sub-section number, as the numbers
+    /// of cards moved to another sub-section are no longer in `cards`
+    #[serde(default)]
+    last_cards: BTreeMap<String, usize>,
+}
+
+impl Numbering {
+    /// Reads the numbering saved by a previous run, an empty one if there is none
+    pub fn load(path: &Path) -> io::Result<Numbering> {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Numbering::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+    }
+
+    /// Numbers everything which is not numbered yet, in the order of the sorted cards
+    pub fn assign(&mut self, sorted_cards: &BTreeMap<String, BTreeMap<String, Vec<PldCard>>>) {
+        for (section, sub_sections) in sorted_cards {
+            let section_number = next_number(&mut self.sections, section);
+            let numbers = self.sub_sections.entry(section.clone()).or_default();
+
+            for (sub_section, cards) in sub_sections {
+                let prefix = format!("{}.{}.", section_number, next_number(numbers, sub_section));
+
+                for card in cards {
+                    // Cards moved to another sub-section are numbered again
+                    let numbered = self
+                        .cards
+                        .get(&card.key)
+                        .is_some_and(|number| number.starts_with(&prefix));
+                    if numbered {
+                        continue;
+                    }
+
+                    let last = self
+                        .cards
+                        .values()
+                        .filter_map(|number| number.strip_prefix(&prefix)?.parse::<usize>().ok())
+                        .chain(self.last_cards.get(&prefix).copied())
+                        .max()
+                        .unwrap_or_default();
+                    self.last_cards.insert(prefix.clone(), last + 1);
+                    self.cards
+                        .insert(card.key.clone(), format!("{}{}", prefix, last + 1));
+                }
+            }
+        }
+    }
+
+    pub fn section(&self, section: &str) -> Option<String> {
+        self.sections.get(section).map(usize::to_string)
+    }
+
+    pub fn sub_section(&self, section: &str, sub_section: &str) -> Option<String> {
+        Some(format!(
+            "{}.{}",
+            self.sections.get(section)?,
+            self.sub_sections.get(section)?.get(sub_section)?
+        ))
+    }
+
+    pub fn card(&self, card: &PldCard) -> Option<String> {
+        self.cards.get(&card.key).cloned()
+    }
+}
+
+/// Number of `name`, numbering it after the last one when it has none
+fn next_number(numbers: &mut BTreeMap<String, usize>, name: &str) -> usize {
+    let next = numbers.values().max().unwrap_or(&0) + 1;
+
+    *numbers.entry(name.to_string()).or_insert(next)
+}
+
+/// Orders numbers component by component, so that `1.10` comes after `1.9`
+pub fn number_order(number: &str) -> Vec<usize> {
+    number
+        .split('.')
+        .map(|part| part.parse().unwrap_or(usize::MAX))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::CardSchema,
+        source::card::{fixtures, ProjectCard},
+    };
+
+    /// Cards sorted by section, given as `(section, sub-section, key)`
+    fn sorted(cards: &[(&str, &str, &str)]) -> BTreeMap<String, BTreeMap<String, Vec<PldCard>>> {
+        let mut sorted: BTreeMap<String, BTreeMap<String, Vec<PldCard>>> = BTreeMap::new();
+
+        for (section, sub_section, key) in cards {
+            let card = ProjectCard {
+                id: Some(key.to_string()),
+                ..fixtures::card(key, fixtures::BODY)
+            };
+            sorted
+                .entry(section.to_string())
+                .or_default()
+                .entry(sub_section.to_string())
+                .or_default()
+                .push(PldCard::new(&card, &CardSchema::default()).unwrap());
+        }
+
+        sorted
+    }
+
+    fn number(numbering: &Numbering, key: &str) -> Option<String> {
+        numbering.cards.get(key).cloned()
+    }
+
+    #[test]
+    fn everything_is_numbered_in_order() {
+        let mut numbering = Numbering::default();
+        numbering.assign(&sorted(&[
+            ("A", "a", "1"),
+            ("A", "a", "2"),
+            ("A", "b", "3"),
+            ("B", "c", "4"),
+        ]));
+
+        assert_eq!(numbering.section("B").as_deref(), Some("2"));
+        assert_eq!(numbering.sub_section("A", "b").as_deref(), Some("1.2"));
+        assert_eq!(number(&numbering, "2").as_deref(), Some("1.1.2"));
+        assert_eq!(number(&numbering, "3").as_deref(), Some("1.2.1"));
+        assert_eq!(number(&numbering, "4").as_deref(), Some("2.1.1"));
+    }
+
+    #[test]
+    fn moved_cards_are_numbered_again() {
+        let mut numbering = Numbering::default();
+        numbering.assign(&sorted(&[
+            ("A", "a", "1"),
+            ("A", "a", "2"),
+            ("A", "b", "3"),
+        ]));
+        numbering.assign(&sorted(&[
+            ("A", "a", "1"),
+            ("A", "b", "3"),
+            ("A", "b", "2"),
+        ]));
+
+        assert_eq!(number(&numbering, "1").as_deref(), Some("1.1.1"));
+        assert_eq!(number(&numbering, "3").as_deref(), Some("1.2.1"));
+        assert_eq!(number(&numbering, "2").as_deref(), Some("1.2.2"));
+    }
+
+    #[test]
+    fn numbers_are_never_given_again() {
+        let mut numbering = Numbering::default();
+        numbering.assign(&sorted(&[("A", "a", "1"), ("A", "a", "2")]));
+        // Card 2 is moved away, its number is not given to the next card of the sub-section
+        numbering.assign(&sorted(&[("A", "a", "1"), ("A", "b", "2")]));
+        numbering.assign(&sorted(&[
+            ("A", "a", "1"),
+            ("A", "a", "3"),
+            ("A", "b", "2"),
+        ]));
+
+        assert_eq!(number(&numbering, "2").as_deref(), Some("1.2.1"));
+        assert_eq!(number(&numbering, "3").as_deref(), Some("1.1.3"));
+    }
+
+    #[test]
+    fn numbering_saved_without_last_numbers_is_read() {
+        let numbering: Numbering = serde_json::from_str(
+            r#"{"sections": {"A": 1}, "sub_sections": {"A": {"a": 1}}, "cards": {"1": "1.1.1"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(number(&numbering, "1").as_deref(), Some("1.1.1"));
+    }
+
+    #[test]
+    fn numbers_are_ordered_component_by_component() {
+        let mut numbers = vec!["1.10", "2", "1.9", "1.9.1", "1"];
+        numbers.sort_by_key(|number| number_order(number));
+
+        assert_eq!(numbers, ["1", "1.9", "1.9.1", "1.10", "2"]);
+    }
+}
@@ -0,0 +1,104 @@
+use regress::Regex;
+
+use super::{sections::lines_outside_code, wish::markers_pattern, FLAGS};
+use crate::config::CardSchema;
+
+/// Reads the references of the lines starting with a dependency marker, ie: `Depends on #12, #14`
+/// or `Blocked by 2.1 API`, code blocks excluded
+///
+/// References are separated by semicolons, or by commas when every one of them is an issue
+/// reference or a link so that titles may hold commas. A markdown link refers to its target.
+pub fn dependency_references(content: &str, schema: &CardSchema) -> Vec<String> {
+    let line_regex = Regex::with_flags(
+        &format!(
+            r"^\s*(?:[-*+]\s+)?(?:\*\*)?\s*(?:{})(?!\w)\s*:?\s*(?:\*\*)?\s*:?\s*(.+)$",
+            markers_pattern(&schema.dependency_markers)
+        ),
+        FLAGS,
+    )
+    .unwrap();
+    let link_regex = Regex::with_flags(
+        r"^(?:(?:[\w.-]+/[\w.-]+)?#\d+|https?://\S+|\[[^\]]*\]\([^)]*\))$",
+        FLAGS,
+    )
+    .unwrap();
+
+    lines_outside_code(content)
+        .filter_map(|line| Some(&line[line_regex.find(line)?.group(1)?]))
+        .flat_map(|references| {
+            references
+                .trim()
+                .trim_end_matches('.')
+                .split(';')
+                .flat_map(|group| {
+                    let parts: Vec<&str> = group.split(',').collect();
+                    let all_links = parts.iter().all(|part| {
+                        link_regex
+                            .find(part.trim().trim_matches(['"', '\'', '`']))
+                            .is_some()
+                    });
+
+                    if all_links {
+                        parts
+                    } else {
+                        vec![group]
+                    }
+                })
+                .map(reference)
+                .filter(|reference| !reference.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Strips the quotes around a reference, keeping only the target of links
+fn reference(text: &str) -> String {
+    let text = text.trim().trim_matches(['"', '\'', '`']);
+
+    match text
+        .strip_prefix('[')
+        .and_then(|link| link.split_once("]("))
+    {
+        Some((_, target)) => target.trim_end_matches(')').to_string(),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn references(content: &str) -> Vec<String> {
+        dependency_references(content, &CardSchema::default())
+    }
+
+    #[test]
+    fn references_are_read_from_marker_lines() {
+        assert_eq!(
+            references(
+                "Depends on #12, `owner/web#3`.\n- **Blocked by:** [API](https://host/1); \"2.1 API\""
+            ),
+            ["#12", "owner/web#3", "https://host/1", "2.1 API"]
+        );
+    }
+
+    #[test]
+    fn titles_are_only_split_on_semicolons() {
+        assert_eq!(
+            references("Blocked by Login, signup and reset\nDepends on #1, [Doc](https://host/2)"),
+            ["Login, signup and reset", "#1", "https://host/2"]
+        );
+        assert_eq!(
+            references("Depends on 2.1 API, v2; #4, https://host/5"),
+            ["2.1 API, v2", "#4", "https://host/5"]
+        );
+    }
+
+    #[test]
+    fn code_blocks_are_ignored() {
+        assert_eq!(
+            references("````\n```\nDepends on #1\n```\n````\n\n    Depends on #2\n\nDépend de #3"),
+            ["#3"]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::{
+    fmt::{self, Display},
+    iter::Sum,
+    ops::Add,
+};
+
+/// Item of the definition of done, `- [x]` items are checked while `- [ ]` and plain bullets
+/// are not
+#[derive(Debug, Default, Clone)]
+pub struct DodItem {
+    pub text: String,
+    pub checked: bool,
+    pub children: Vec<DodItem>,
+}
+
+impl DodItem {
+    /// Parses every list of the definition of done into a tree of items
+    pub fn from_markdown(dod: &str) -> Vec<DodItem> {
+        let mut roots = Vec::new();
+        // Items currently open, the innermost last
+        let mut open: Vec<DodItem> = Vec::new();
+
+        for event in Parser::new_ext(dod, Options::ENABLE_TASKLISTS) {
+            match event {
+                Event::Start(Tag::Item) => open.push(DodItem::default()),
+                Event::TaskListMarker(checked) => {
+                    if let Some(item) = open.last_mut() {
+                        item.checked = checked;
+                    }
+                }
+                Event::Text(text) | Event::Code(text) => {
+                    // Text of nested items goes to the nested item, which is the last open one
+                    if let Some(item) = open.last_mut() {
+                        item.text.push_str(&text);
+                    }
+                }
+                Event::SoftBreak | Event::HardBreak => {
+                    if let Some(item) = open.last_mut() {
+                        item.text.push(' ');
+                    }
+                }
+                Event::End(TagEnd::Item) => {
+                    let Some(mut item) = open.pop() else {
+                        continue;
+                    };
+                    item.text = item.text.trim().to_string();
+
+                    match open.last_mut() {
+                        Some(parent) => parent.children.push(item),
+                        None => roots.push(item),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        roots
+    }
+
+    /// Completion of the item, counted on the items without children
+    ///
+    /// A checked item counts all of its children as met.
+    pub fn completion(&self) -> Completion {
+        if self.children.is_empty() {
+            return Completion {
+                met: self.checked as usize,
+                total: 1,
+            };
+        }
+
+        let children: Completion = self.children.iter().map(DodItem::completion).sum();
+        if self.checked {
+            Completion {
+                met: children.total,
+                total: children.total,
+            }
+        } else {
+            children
+        }
+    }
+}
+
+/// Number of definition of done items met out of the total, summed over cards to roll the
+/// completion up to sub-sections, sections and the whole document
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Completion {
+    pub met: usize,
+    pub total: usize,
+}
+
+impl Completion {
+    /// Percentage of items met, `None` when there is no item at all
+    pub fn percentage(&self) -> Option<f32> {
+        (self.total > 0).then(|| self.met as f32 * 100.0 / self.total as f32)
+    }
+}
+
+impl Add for Completion {
+    type Output = Completion;
+
+    fn add(self, rhs: Completion) -> Completion {
+        Completion {
+            met: self.met + rhs.met,
+            total: self.total + rhs.total,
+        }
+    }
+}
+
+impl Sum for Completion {
+    fn sum<I: Iterator<Item = Completion>>(iter: I) -> Completion {
+        iter.fold(Completion::default(), Add::add)
+    }
+}
+
+impl Display for Completion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.percentage() {
+            Some(percentage) => write!(f, "{}/{} ({:.0}%)", self.met, self.total, percentage),
+            None => write!(f, "no item"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completion(dod: &str) -> Completion {
+        DodItem::from_markdown(dod)
+            .iter()
+            .map(DodItem::completion)
+            .sum()
+    }
+
+    #[test]
+    fn items_are_parsed_into_a_tree() {
+        let items = DodItem::from_markdown(
+            "- [x] tests pass\n- [ ] docs\n  - README\n  - [X] `--help`\n\n1. plain\n   text",
+        );
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].text, "tests pass");
+        assert!(items[0].checked);
+        assert_eq!(items[1].text, "docs");
+        assert!(!items[1].checked);
+        assert_eq!(items[1].children[0].text, "README");
+        assert!(!items[1].children[0].checked);
+        assert_eq!(items[1].children[1].text, "--help");
+        assert!(items[1].children[1].checked);
+        assert_eq!(items[2].text, "plain text");
+        assert!(items[2].children.is_empty());
+    }
+
+    #[test]
+    fn text_outside_of_lists_is_ignored() {
+        assert!(DodItem::from_markdown("Some text\n\n```\n- code\n```").is_empty());
+    }
+
+    #[test]
+    fn completion_is_counted_on_leaf_items() {
+        assert_eq!(
+            completion("- [x] a\n- [ ] b\n  - [x] c\n  - [ ] d\n- e"),
+            Completion { met: 2, total: 4 }
+        );
+    }
+
+    #[test]
+    fn checked_parents_meet_all_of_their_children() {
+        assert_eq!(
+            completion("- [x] a\n  - [ ] b\n  - [ ] c\n    - [ ] d\n    - [ ] e"),
+            Completion { met: 3, total: 3 }
+        );
+    }
+
+    #[test]
+    fn completion_rolls_up() {
+        let cards = [
+            completion("- [x] a\n- [ ] b"),
+            completion(""),
+            completion("- [x] c"),
+        ];
+        let total: Completion = cards.into_iter().sum();
+
+        assert_eq!(total, Completion { met: 2, total: 3 });
+        assert_eq!(total.to_string(), "2/3 (67%)");
+        assert_eq!(Completion::default().to_string(), "no item");
+    }
+}
@@ -0,0 +1,326 @@
+use colored::Colorize;
+use std::fmt::{self, Display};
+
+use super::CardSection;
+use crate::config::CardSchema;
+
+/// Position in a card body, both starting at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn from_offset(content: &str, offset: usize) -> Location {
+        let before = &content[..offset];
+
+        Location {
+            line: before.matches('\n').count() + 1,
+            column: before
+                .rsplit('\n')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .count()
+                + 1,
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Debug)]
+pub enum ParsingError {
+    /// None of the headings of a required section were found
+    SectionMissing {
+        section: CardSection,
+        /// Top level headings matching no section, which may be misspelled
+        unknown_headings: Vec<(String, Location)>,
+    },
+    /// The section holds a heading where none is expected
+    SectionContainsHeader {
+        section: CardSection,
+        header: String,
+        location: Location,
+    },
+    /// The section is empty or its content does not have the expected shape
+    SectionMissingInformation {
+        section: CardSection,
+        location: Location,
+    },
+    /// Several headings introduce the same section
+    TooManyMatches {
+        section: CardSection,
+        locations: Vec<Location>,
+    },
+}
+
+impl ParsingError {
+    /// Identifier of the kind of error, used in machine readable reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParsingError::SectionMissing { .. } => "section_missing",
+            ParsingError::SectionContainsHeader { .. } => "section_contains_header",
+            ParsingError::SectionMissingInformation { .. } => "section_missing_information",
+            ParsingError::TooManyMatches { .. } => "too_many_matches",
+        }
+    }
+
+    /// First position of the error in the card body, if it has one
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            ParsingError::SectionMissing { .. } => None,
+            ParsingError::SectionContainsHeader { location, .. }
+            | ParsingError::SectionMissingInformation { location, .. } => Some(*location),
+            ParsingError::TooManyMatches { locations, .. } => locations.first().copied(),
+        }
+    }
+
+    /// Describes how the card body can be fixed
+    pub fn suggestion(&self, schema: &CardSchema) -> String {
+        match self {
+            ParsingError::SectionMissing {
+                section,
+                unknown_headings,
+            } if !unknown_headings.is_empty() => format!(
+                "Rename one of the headings {} to \"# {}\" or add it to the aliases of [card-schema.{}]",
+                unknown_headings
+                    .iter()
+                    .map(|(heading, location)| format!("\"{}\" ({})", heading, location))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                section.name(),
+                section.schema_key()
+            ),
+            ParsingError::SectionMissing { section, .. } => format!(
+                "Add a \"# {}\" heading as shown in card_format.md",
+                section.name()
+            ),
+            ParsingError::SectionContainsHeader { header, .. } => format!(
+                "Move \"{}\" and its content to a section of its own after the user wish",
+                header
+            ),
+            ParsingError::SectionMissingInformation {
+                section: CardSection::UserWish,
+                ..
+            } => format!(
+                "Write each wish as \"**{}** <user>\" followed by \"**{}** <action>\" and optionally \"**{}** <benefit>\"",
+                schema.user_markers.first().map_or("", String::as_str),
+                schema.action_markers.first().map_or("", String::as_str),
+                schema.benefit_markers.first().map_or("", String::as_str)
+            ),
+            ParsingError::SectionMissingInformation { section, .. } => format!(
+                "Fill the section or set optional = true in [card-schema.{}]",
+                section.schema_key()
+            ),
+            ParsingError::TooManyMatches { .. } => {
+                "Merge the sections into a single one".to_string()
+            }
+        }
+    }
+}
+
+impl Display for ParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsingError::SectionMissing { section, .. } => {
+                write!(f, "Section \"{}\" is missing", section.name())
+            }
+            ParsingError::SectionContainsHeader {
+                section,
+                header,
+                location,
+            } => write!(
+                f,
+                "Section \"{}\" contains the heading \"{}\" at {}",
+                section.name(),
+                header,
+                location
+            ),
+            ParsingError::SectionMissingInformation { section, location } => write!(
+                f,
+                "Section \"{}\" at {} is empty or incomplete",
+                section.name(),
+                location
+            ),
+            ParsingError::TooManyMatches { section, locations } => write!(
+                f,
+                "Section \"{}\" appears {} times, at {}",
+                section.name(),
+                locations.len(),
+                locations
+                    .iter()
+                    .map(Location::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" and ")
+            ),
+        }
+    }
+}
+
+/// Parsing errors of a card skipped from the PLD
+pub struct CardDiagnostics {
+    pub name: String,
+    pub url: Option<String>,
+    pub errors: Vec<ParsingError>,
+}
+
+/// Prints the errors of every skipped card, grouped by card
+pub fn print_report(diagnostics: &[CardDiagnostics], schema: &CardSchema) {
+    if !diagnostics.is_empty() {
+        print!("{}", report(diagnostics, schema));
+    }
+}
+
+fn report(diagnostics: &[CardDiagnostics], schema: &CardSchema) -> String {
+    let mut report = format!(
+        "{} {} card(s) skipped due to parsing failures :\n",
+        "WARNING:".yellow(),
+        diagnostics.len()
+    );
+
+    for card in diagnostics {
+        match &card.url {
+            Some(url) => report += &format!("\n  \"{}\" ({})\n", card.name.trim().blue(), url),
+            None => report += &format!("\n  \"{}\"\n", card.name.trim().blue()),
+        }
+
+        for error in &card.errors {
+            report += &format!("    - {}\n", error.to_string().red());
+            report += &format!("      {}\n", error.suggestion(schema));
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parsing::PldCard,
+        source::card::fixtures::{self, BODY},
+    };
+
+    fn errors(content: &str) -> Vec<ParsingError> {
+        PldCard::new(&fixtures::card("Login", content), &CardSchema::default()).unwrap_err()
+    }
+
+    #[test]
+    fn locations_count_lines_and_characters() {
+        let content = "# Titre\n\nÉté\n# DOD";
+
+        assert_eq!(
+            Location::from_offset(content, 0),
+            Location { line: 1, column: 1 }
+        );
+        assert_eq!(
+            Location::from_offset(content, content.find("té").unwrap()),
+            Location { line: 3, column: 2 }
+        );
+        assert_eq!(
+            Location::from_offset(content, content.find("# DOD").unwrap()).to_string(),
+            "line 4, column 1"
+        );
+    }
+
+    #[test]
+    fn missing_section() {
+        let errors = errors(&BODY.replace("# DOD\n\n- done", ""));
+
+        let [error] = errors.as_slice() else {
+            panic!("expected a single error, got {:?}", errors)
+        };
+        assert_eq!(error.kind(), "section_missing");
+        assert_eq!(error.location(), None);
+        assert_eq!(error.to_string(), "Section \"DOD\" is missing");
+        assert_eq!(
+            error.suggestion(&CardSchema::default()),
+            "Add a \"# DOD\" heading as shown in card_format.md"
+        );
+    }
+
+    #[test]
+    fn misspelled_heading_is_suggested_with_its_location() {
+        let errors = errors(&BODY.replace("# Description", "# Descritpion"));
+
+        let [error] = errors.as_slice() else {
+            panic!("expected a single error, got {:?}", errors)
+        };
+        let ParsingError::SectionMissing {
+            unknown_headings, ..
+        } = error
+        else {
+            panic!("expected a missing section, got {:?}", error)
+        };
+        assert_eq!(
+            unknown_headings,
+            &[("Descritpion".to_string(), Location { line: 7, column: 1 })]
+        );
+        assert_eq!(
+            error.suggestion(&CardSchema::default()),
+            "Rename one of the headings \"Descritpion\" (line 7, column 1) to \"# Description\" \
+             or add it to the aliases of [card-schema.description]"
+        );
+    }
+
+    #[test]
+    fn errors_inside_sections_are_located() {
+        let errors = errors(&format!("{}\n\n# DOD\n\n- again", BODY.replace("Text", "")));
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].kind(), "section_missing_information");
+        assert_eq!(errors[0].location(), Some(Location { line: 7, column: 1 }));
+        assert_eq!(
+            errors[0].suggestion(&CardSchema::default()),
+            "Fill the section or set optional = true in [card-schema.description]"
+        );
+        assert_eq!(errors[1].kind(), "too_many_matches");
+        assert_eq!(
+            errors[1].location(),
+            Some(Location {
+                line: 11,
+                column: 1
+            })
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "Section \"DOD\" appears 2 times, at line 11, column 1 and line 15, column 1"
+        );
+    }
+
+    #[test]
+    fn report_groups_errors_by_card() {
+        let diagnostics = vec![
+            CardDiagnostics {
+                name: " Login ".to_string(),
+                url: Some("https://example.com/1".to_string()),
+                errors: errors(&BODY.replace("# DOD\n\n- done", "")),
+            },
+            CardDiagnostics {
+                name: "Signup".to_string(),
+                url: None,
+                errors: errors(&BODY.replace("# DOD\n\n- done", "")),
+            },
+        ];
+
+        let report = report(&diagnostics, &CardSchema::default());
+
+        assert!(report.contains("2 card(s) skipped due to parsing failures :"));
+        assert!(report.contains("Login"));
+        assert!(report.contains("\" (https://example.com/1)\n"));
+        assert!(report.contains("Signup"));
+        assert_eq!(report.matches("Section \"DOD\" is missing").count(), 2);
+        assert_eq!(
+            report
+                .matches("      Add a \"# DOD\" heading as shown in card_format.md\n")
+                .count(),
+            2
+        );
+    }
+}
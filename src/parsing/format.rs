@@ -0,0 +1,374 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use super::{
+    sections::{code_lines, normalize_title},
+    wish::wish_words,
+    CardSection, PldCard,
+};
+use crate::config::CardSchema;
+
+impl PldCard {
+    /// Writes the card body back in the layout of `card_format.md`, `None` when the user wish
+    /// section holds text the wishes do not capture, which would be lost
+    ///
+    /// Sections of the schema use their default heading, even when empty, wishes use the first
+    /// markers of the schema, blank lines are collapsed and definition of done bullets are
+    /// written with `-`, nested items being aligned with the text of their parent. Code blocks
+    /// are kept untouched, as well as the order of the sections and the headings and text of the
+    /// sections outside of the schema.
+    pub fn to_markdown(&self, schema: &CardSchema) -> Option<String> {
+        let markers = [
+            first_marker(&schema.user_markers),
+            first_marker(&schema.action_markers),
+            first_marker(&schema.benefit_markers),
+        ];
+        let wishes = self.wishes_markdown(markers);
+
+        if wish_words(&self.user_wish, schema) != wish_words(&wishes, schema) {
+            return None;
+        }
+
+        let mut blocks = Vec::new();
+
+        if !self.preamble.is_empty() {
+            blocks.push(collapse_blank_lines(&self.preamble));
+        }
+
+        let mut other_sections = self.other_sections.iter();
+
+        for title in &self.section_order {
+            let normalized = normalize_title(title);
+            let section = CardSection::ALL
+                .into_iter()
+                .find(|section| section.headings(schema).contains(&normalized));
+
+            let block = match section {
+                Some(CardSection::UserWish) => {
+                    format!("# {}\n\n{}", CardSection::UserWish.name(), wishes)
+                }
+                Some(CardSection::Description) => format!(
+                    "# {}\n\n{}",
+                    CardSection::Description.name(),
+                    collapse_blank_lines(&self.description)
+                ),
+                Some(CardSection::Dod) => format!(
+                    "# {}\n\n{}",
+                    CardSection::Dod.name(),
+                    normalize_bullets(&self.dod)
+                ),
+                None => {
+                    let Some((heading, body)) = other_sections.next() else {
+                        continue;
+                    };
+                    let extra = schema
+                        .extra_sections
+                        .iter()
+                        .find(|extra| normalize_title(extra) == normalized);
+
+                    match extra {
+                        Some(extra) => format!("# {}\n\n{}", extra, collapse_blank_lines(body)),
+                        None => format!("{}\n\n{}", heading, collapse_blank_lines(body)),
+                    }
+                }
+            };
+
+            blocks.push(block.trim_end().to_string());
+        }
+
+        Some(blocks.join("\n\n") + "\n")
+    }
+}
+
+fn first_marker(markers: &[String]) -> &str {
+    markers.first().map_or("", |marker| marker.trim())
+}
+
+/// Replaces runs of blank lines outside of code blocks with a single one and removes the blank
+/// lines around the text, the indentation of the first line is kept
+fn collapse_blank_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    // Leading blank lines are dropped as if a blank line came before
+    let mut previous_blank = true;
+
+    for (line, in_code) in code_lines(text) {
+        let blank = !in_code && line.trim().is_empty();
+        if !(blank && previous_blank) {
+            lines.push(if blank { "" } else { line });
+        }
+        previous_blank = blank;
+    }
+
+    if lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+/// Writes every bullet with `-` and aligns nested list items with the text of their parent item
+///
+/// Items are found by the markdown parser, and each item is moved along with all of its lines so
+/// that paragraphs and code blocks stay in the item they belong to. Items within block quotes or
+/// indented with tabs are left as they are.
+fn normalize_bullets(text: &str) -> String {
+    let text = collapse_blank_lines(text);
+
+    let mut line_starts = vec![0];
+    line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+    let line_of = |offset: usize| line_starts.partition_point(|start| *start <= offset) - 1;
+    let line_text = |line: usize| {
+        let end = line_starts
+            .get(line + 1)
+            .map_or(text.len(), |next| next - 1);
+        &text[line_starts[line]..end]
+    };
+
+    // Columns added to, or removed from, the start of each line
+    let mut shifts = vec![0isize; line_starts.len()];
+    let mut bullets = Vec::new();
+    // Whether each open list is ordered, and the column of the text of each open item
+    let mut ordered_lists = Vec::new();
+    let mut parent_columns: Vec<isize> = Vec::new();
+    let mut quote_depth = 0;
+
+    for (event, range) in Parser::new_ext(&text, Options::ENABLE_TASKLISTS).into_offset_iter() {
+        match event {
+            Event::Start(Tag::BlockQuote(_)) => quote_depth += 1,
+            Event::End(TagEnd::BlockQuote(_)) => quote_depth -= 1,
+            Event::Start(Tag::List(first)) => ordered_lists.push(first.is_some()),
+            Event::End(TagEnd::List(_)) => {
+                ordered_lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                // The range of nested items may start within their indentation
+                let marker = range.start + text[range.start..].len()
+                    - text[range.start..].trim_start_matches(' ').len();
+                let line = line_of(marker);
+                let column = marker - line_starts[line];
+                let ordered = ordered_lists.last().copied().unwrap_or_default();
+
+                let marker_width = if ordered {
+                    text[marker..]
+                        .chars()
+                        .take_while(char::is_ascii_digit)
+                        .count()
+                        + 1
+                } else {
+                    if text[marker..].starts_with(['*', '+']) {
+                        bullets.push(marker);
+                    }
+                    1
+                };
+
+                // Lines of the item, nested items included
+                let last_line = line_of(range.end.saturating_sub(1).max(marker));
+                let lines = line..=last_line;
+
+                let movable = quote_depth == 0
+                    && line_text(line)[..column].chars().all(|c| c == ' ')
+                    && lines.clone().all(|l| {
+                        let text = line_text(l);
+                        !text[..text.len() - text.trim_start().len()].contains('\t')
+                    });
+
+                let current = column as isize + shifts[line];
+                let target = parent_columns.last().copied().unwrap_or(0);
+                if movable && current != target {
+                    for l in lines {
+                        shifts[l] += target - current;
+                    }
+                }
+
+                // The text follows the marker after one to four spaces, a single one otherwise
+                let after_marker = &line_text(line)[column + marker_width..];
+                let spaces = after_marker.len() - after_marker.trim_start_matches(' ').len();
+                let gap = if (1..=4).contains(&spaces) && spaces < after_marker.len() {
+                    spaces
+                } else {
+                    1
+                };
+                parent_columns.push((column + marker_width + gap) as isize + shifts[line]);
+            }
+            Event::End(TagEnd::Item) => {
+                parent_columns.pop();
+            }
+            _ => (),
+        }
+    }
+
+    let mut output = text.clone();
+    for bullet in bullets {
+        output.replace_range(bullet..bullet + 1, "-");
+    }
+
+    output
+        .split('\n')
+        .zip(shifts)
+        .map(|(line, shift)| {
+            if line.trim().is_empty() {
+                line.to_string()
+            } else if shift >= 0 {
+                format!("{}{}", " ".repeat(shift as usize), line)
+            } else {
+                let indentation = line.len() - line.trim_start_matches(' ').len();
+                line[indentation.min(-shift as usize)..].to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::SectionSchema,
+        source::card::fixtures::{self, BODY},
+    };
+
+    fn fix(body: &str) -> Option<String> {
+        fix_with(body, &CardSchema::default())
+    }
+
+    fn fix_with(body: &str, schema: &CardSchema) -> Option<String> {
+        PldCard::new(&fixtures::card("Card", body), schema)
+            .unwrap()
+            .to_markdown(schema)
+    }
+
+    /// Fixes a card whose definition of done is `dod`, returning the fixed definition of done
+    fn fix_dod(dod: &str) -> String {
+        let fixed = fix(&BODY.replace("# DOD\n\n- done", &format!("# DOD\n\n{}", dod))).unwrap();
+
+        fixed
+            .split_once("# DOD\n\n")
+            .unwrap()
+            .1
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn formatted_cards_are_kept_as_is() {
+        let body =
+            "Context\n\n# Notes\n\n# User wish\n\n**As the:** user\n\n**I want to:** log in\n\n\
+                    **So that:** my data is kept\n\n# DOD\n\n- [x] form\n  - [ ] tests\n\n\
+                    # Description\n\nSee below\n\n```\n\n\nraw\n```\n";
+
+        assert_eq!(fix(body).unwrap(), body);
+    }
+
+    #[test]
+    fn fixing_is_idempotent() {
+        let body = "#User wish\nAs a user, I want to log in so that my data is kept\n\n\n\
+                    ## description :\nText\n\n\n\n    indented code\n\n\n    kept\n\n\
+                    #DOD:\n* a\n    + b\n\n1. first\n    * nested\n# Notes";
+        let fixed = fix(body).unwrap();
+
+        assert_eq!(fix(&fixed).unwrap(), fixed);
+        assert!(fixed.starts_with("# User wish\n\n**As the:** user\n\n**I want to:** log in"));
+        assert!(fixed.contains("# Description\n\nText\n\n    indented code\n\n\n    kept\n\n"));
+        assert!(fixed.ends_with("# DOD\n\n- a\n  - b\n\n1. first\n   - nested\n\n# Notes\n"));
+    }
+
+    #[test]
+    fn nested_items_stay_under_numbered_items() {
+        assert_eq!(fix_dod("1. first\n   - nested"), "1. first\n   - nested");
+        assert_eq!(
+            fix_dod("10. tenth\n    * nested"),
+            "10. tenth\n    - nested"
+        );
+    }
+
+    #[test]
+    fn items_move_along_with_their_content() {
+        assert_eq!(
+            fix_dod("- a\n    * b\n\n      text of b\n\n      ```\n      code\n      ```"),
+            "- a\n  - b\n\n    text of b\n\n    ```\n    code\n    ```"
+        );
+    }
+
+    #[test]
+    fn code_blocks_are_not_list_items() {
+        assert_eq!(
+            fix_dod("Paragraph\n\n        - code?\n\n\n        * kept"),
+            "Paragraph\n\n        - code?\n\n\n        * kept"
+        );
+        assert_eq!(fix_dod("    - code?"), "    - code?");
+    }
+
+    #[test]
+    fn nested_fences_are_kept() {
+        let dod = "- item\n\n````md\n```\n\n\n* not an item\n```\n````";
+
+        assert_eq!(fix_dod(dod), dod);
+    }
+
+    #[test]
+    fn quoted_items_are_kept() {
+        assert_eq!(
+            fix_dod("> * quoted\n>     * nested"),
+            "> - quoted\n>     - nested"
+        );
+    }
+
+    #[test]
+    fn sections_keep_their_order() {
+        let (wish, dod) = BODY.split_once("\n\n# DOD").unwrap();
+        let fixed = fix(&format!("# Notes\n\nFirst\n\n# DOD{}\n\n{}", dod, wish)).unwrap();
+
+        assert!(fixed.starts_with("# Notes\n\nFirst\n\n# DOD\n\n- done\n\n# User wish"));
+    }
+
+    #[test]
+    fn repeated_extra_sections_are_all_kept() {
+        let schema = CardSchema {
+            extra_sections: vec!["Risks".to_string()],
+            ..CardSchema::default()
+        };
+        let body = format!("{}\n\n# Risks\n\nFirst\n\n## risks :\n\nSecond\n", BODY);
+
+        let fixed = fix_with(&body, &schema).unwrap();
+
+        assert!(fixed.ends_with("# Risks\n\nFirst\n\n# Risks\n\nSecond\n"));
+    }
+
+    #[test]
+    fn unknown_headings_are_kept_as_written() {
+        let body = format!(
+            "{}\n\n## Notes on [login](https://example.com) *soon*\n\nText\n\n### Empty",
+            BODY
+        );
+
+        let fixed = fix(&body).unwrap();
+
+        assert!(fixed
+            .ends_with("## Notes on [login](https://example.com) *soon*\n\nText\n\n### Empty\n"));
+    }
+
+    #[test]
+    fn empty_optional_sections_keep_their_heading() {
+        let schema = CardSchema {
+            description: SectionSchema {
+                optional: true,
+                ..SectionSchema::default()
+            },
+            extra_sections: vec!["Risks".to_string()],
+            ..CardSchema::default()
+        };
+        let body = format!("{}\n\n# Risks", BODY.replace("Text", ""));
+
+        let fixed = fix_with(&body, &schema).unwrap();
+
+        assert!(fixed.contains("log in\n\n# Description\n\n# DOD\n\n- done\n\n# Risks\n"));
+        assert_eq!(fix_with(&fixed, &schema).unwrap(), fixed);
+    }
+
+    #[test]
+    fn text_before_the_wishes_is_kept() {
+        let fixed = fix(&BODY.replace("**As the:**", "Context: the team.\n**As the:**")).unwrap();
+
+        assert!(fixed.starts_with("# User wish\n\nContext: the team.\n\n**As the:** user"));
+        assert_eq!(fix(&fixed).unwrap(), fixed);
+    }
+}
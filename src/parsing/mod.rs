@@ -1,17 +1,23 @@
-use std::{collections::BTreeMap, fmt};
-
-use regress::{Flags, Regex};
+mod dependencies;
+mod dod;
+mod error;
+mod format;
+mod sections;
+mod wish;
 
-use crate::github::card::ProjectCard;
+use std::{collections::BTreeMap, fmt};
 
-// Section parsing regex's
-const USER_WISH_REGEX: &str = r"(?<=^# *User wish$\s+)\S(?:.|\s)*?(?=\n+# *Description)";
-const DESCRIPTION_REGEX: &str = r"(?<=^# *Description$\s+)\S(?:\s|.)*?(?=\s+# *DOD)";
-const DOD_REGEX: &str = r"(?<=^# *DOD$\s+)\S(?:\n|.)*\S$";
+use regress::Flags;
 
-// User wish parsing regex's
-const USER_WISH_INTERIOR: &str =
-    r"\*\*as the:\*\*\s+?(.+?$)(?:\s)+?\*\*i want to:\*\*((?:.|\s)+.+)";
+use crate::{
+    config::{CardSchema, SectionSchema},
+    source::card::{FieldValue, ProjectCard},
+};
+use dependencies::dependency_references;
+pub use dod::{Completion, DodItem};
+pub use error::{print_report, CardDiagnostics, Location, ParsingError};
+use sections::{normalize_title, preamble, split_sections, Section};
+pub use wish::UserWish;
 
 // Flags to be used
 const FLAGS: Flags = Flags {
@@ -22,120 +28,443 @@ const FLAGS: Flags = Flags {
     unicode: false,
 };
 
-#[derive(Debug)]
+/// Markers of the user, action and benefit of the wishes in the PLD
+const WISH_MARKERS: [&str; 3] = ["As a:", "I want to:", "So that:"];
+
+#[derive(Debug, Clone, Copy)]
 pub enum CardSection {
     UserWish,
     Description,
     Dod,
 }
 
-#[derive(Debug)]
-pub enum ParsingError {
-    SectionMissing(CardSection),
-    _SectionContainsHeader(CardSection),
-    SectionMissingInformation(CardSection),
-    _TooManyMatches(CardSection),
-}
+impl CardSection {
+    const ALL: [CardSection; 3] = [
+        CardSection::UserWish,
+        CardSection::Description,
+        CardSection::Dod,
+    ];
 
-/// This is the first part of the card
-///
-/// > As the `user`, I want to `action`
-#[derive(Debug)]
-pub struct UserWish {
-    pub user: String,
-    pub action: String,
-}
+    fn schema<'a>(&self, schema: &'a CardSchema) -> &'a SectionSchema {
+        match self {
+            CardSection::UserWish => &schema.user_wish,
+            CardSection::Description => &schema.description,
+            CardSection::Dod => &schema.dod,
+        }
+    }
 
-impl UserWish {
-    pub fn from_markdown(user_wish: &str) -> Result<UserWish, ParsingError> {
-        let user_regex = Regex::with_flags(USER_WISH_INTERIOR, FLAGS).unwrap();
-
-        let matches = user_regex
-            .find(user_wish)
-            .ok_or(ParsingError::SectionMissingInformation(
-                CardSection::UserWish,
-            ))?;
-        let user_group = matches
-            .group(1)
-            .ok_or(ParsingError::SectionMissingInformation(
-                CardSection::UserWish,
-            ))?;
-        let action_group = matches
-            .group(2)
-            .ok_or(ParsingError::SectionMissingInformation(
-                CardSection::UserWish,
-            ))?;
-
-        Ok(UserWish {
-            user: user_wish[user_group].trim().to_string(),
-            action: user_wish[action_group].trim().to_string(),
-        })
+    /// Default heading introducing the section
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardSection::UserWish => "User wish",
+            CardSection::Description => "Description",
+            CardSection::Dod => "DOD",
+        }
+    }
+
+    /// Name of the section table in `[card-schema]`
+    pub fn schema_key(&self) -> &'static str {
+        match self {
+            CardSection::UserWish => "user_wish",
+            CardSection::Description => "description",
+            CardSection::Dod => "dod",
+        }
+    }
+
+    /// Normalized headings introducing the section, the default one followed by the aliases
+    fn headings(&self, schema: &CardSchema) -> Vec<String> {
+        [self.name()]
+            .into_iter()
+            .chain(self.schema(schema).aliases.iter().map(String::as_str))
+            .map(normalize_title)
+            .collect()
     }
+
+    /// Section of the card introduced by one of the section headings
+    ///
+    /// Optional sections may be missing, required ones must also not be empty. `unknown_headings`
+    /// are reported as possible misspellings when the section is missing.
+    fn find<'s, 'a>(
+        &self,
+        schema: &CardSchema,
+        content: &str,
+        sections: &'s [Section<'a>],
+        unknown_headings: &[(String, Location)],
+    ) -> Result<Option<&'s Section<'a>>, ParsingError> {
+        let headings = self.headings(schema);
+        let optional = self.schema(schema).optional;
+
+        let matches: Vec<&Section> = sections
+            .iter()
+            .filter(|section| headings.contains(&normalize_title(&section.title)))
+            .collect();
+
+        match matches.as_slice() {
+            [] if optional => Ok(None),
+            [] => Err(ParsingError::SectionMissing {
+                section: *self,
+                unknown_headings: unknown_headings.to_vec(),
+            }),
+            [section] if section.body.is_empty() && !optional => {
+                Err(ParsingError::SectionMissingInformation {
+                    section: *self,
+                    location: Location::from_offset(content, section.offset),
+                })
+            }
+            [section] => Ok(Some(section)),
+            _ => Err(ParsingError::TooManyMatches {
+                section: *self,
+                locations: matches
+                    .iter()
+                    .map(|section| Location::from_offset(content, section.offset))
+                    .collect(),
+            }),
+        }
+    }
+}
+
+fn find_section<'a>(sections: &[Section<'a>], headings: &[String]) -> Option<&'a str> {
+    sections
+        .iter()
+        .find(|section| headings.contains(&normalize_title(&section.title)))
+        .map(|section| section.body)
 }
 
 /// Main structure representing the parsed contents of a card
 #[derive(Debug)]
 pub struct PldCard {
+    /// Identifier of the card kept across runs, the content ID when the source has one
+    pub key: String,
+    /// Hierarchical number of the card, ie: `2.3.1`, set once every card is sorted
+    pub number: Option<String>,
     pub name: String,
     pub section: String,
     pub sub_section: String,
-    pub wish: UserWish,
+    /// Wishes of the user wish section, empty when the section is optional and missing
+    pub wishes: Vec<UserWish>,
+    /// Text of the user wish section before its first wish, written before the wishes
+    pub wish_context: String,
     pub description: String,
     pub dod: String,
+    /// Items of the definition of done lists
+    pub dod_items: Vec<DodItem>,
+    /// Extra sections of the card schema found on the card, in the schema order
+    pub extra_sections: Vec<(String, String)>,
+    /// Text before the first heading, only kept to write the card back
+    pub preamble: String,
+    /// Heading lines as written and bodies of the sections other than the user wish, the
+    /// description and the definition of done, extra sections included, only kept to write the
+    /// card back
+    pub other_sections: Vec<(String, String)>,
+    /// Titles of every section in the order of the card, only kept to write the card back
+    pub section_order: Vec<String>,
+    /// User wish section as written on the card, to check that writing it back loses no text
+    pub user_wish: String,
     pub working_days: f32,
     pub assignees: Vec<String>,
     pub status: String,
+    /// Custom project fields indexed by field name
+    pub fields: BTreeMap<String, FieldValue>,
+    /// Short references other cards may use to point to this one besides its title
+    pub references: Vec<String>,
+    /// References of the cards this one depends on, from the card body and its metadata
+    pub dependencies: Vec<String>,
+    /// Links to the issues this one depends on given by the source, which may be outside of the
+    /// PLD
+    pub linked_issues: Vec<String>,
+    /// Titles and anchors of the cards of the PLD this one depends on, set once every card is
+    /// known
+    pub depends_on: Vec<(String, String)>,
 }
 
 impl PldCard {
-    pub fn new(card_resp: &ProjectCard) -> Result<PldCard, ParsingError> {
-        let user_wish_regex = Regex::with_flags(USER_WISH_REGEX, FLAGS).unwrap();
-        let description_regex = Regex::with_flags(DESCRIPTION_REGEX, FLAGS).unwrap();
-        let dod_regex = Regex::with_flags(DOD_REGEX, FLAGS).unwrap();
-
-        let wish = match user_wish_regex.find(&card_resp.content) {
-            Some(m) => UserWish::from_markdown(&card_resp.content[m.range])?,
-            None => return Err(ParsingError::SectionMissing(CardSection::UserWish)),
-        };
+    /// Parses the body of a card, returning every problem found when it does not fit the schema
+    pub fn new(card_resp: &ProjectCard, schema: &CardSchema) -> Result<PldCard, Vec<ParsingError>> {
+        let content = card_resp.content.replace("\r\n", "\n");
 
-        let description = match description_regex.find(&card_resp.content) {
-            Some(m) => (&card_resp.content[m.range]).trim().to_string(),
-            None => return Err(ParsingError::SectionMissing(CardSection::Description)),
-        };
+        let mut known: Vec<String> = CardSection::ALL
+            .iter()
+            .flat_map(|section| section.headings(schema))
+            .collect();
+        known.extend(schema.extra_sections.iter().map(|h| normalize_title(h)));
+        let known: Vec<&str> = known.iter().map(String::as_str).collect();
+        let sections = split_sections(&content, &known);
+
+        let unknown_headings: Vec<(String, Location)> = sections
+            .iter()
+            .filter(|section| !known.contains(&normalize_title(&section.title).as_str()))
+            .map(|section| {
+                (
+                    section.title.clone(),
+                    Location::from_offset(&content, section.offset),
+                )
+            })
+            .collect();
+
+        let mut errors = Vec::new();
+        let [wish, description, dod] = CardSection::ALL.map(|section| {
+            section
+                .find(schema, &content, &sections, &unknown_headings)
+                .unwrap_or_else(|e| {
+                    errors.push(e);
+                    None
+                })
+        });
+
+        let (wish_context, wishes) = match wish {
+            Some(section) => {
+                // The wish only holds its two markers, a heading means the next one is misspelled
+                if let Some(sub_heading) = section.sub_headings.first() {
+                    errors.push(ParsingError::SectionContainsHeader {
+                        section: CardSection::UserWish,
+                        header: sub_heading.title.clone(),
+                        location: Location::from_offset(&content, sub_heading.offset),
+                    });
+                }
 
-        let dod = match dod_regex.find(&card_resp.content) {
-            Some(m) => (&card_resp.content[m.range]).trim().to_string(),
-            None => return Err(ParsingError::SectionMissing(CardSection::Dod)),
+                UserWish::from_markdown(section.body, schema).unwrap_or_else(|| {
+                    errors.push(ParsingError::SectionMissingInformation {
+                        section: CardSection::UserWish,
+                        location: Location::from_offset(&content, section.body_offset),
+                    });
+                    (String::new(), Vec::new())
+                })
+            }
+            None => (String::new(), Vec::new()),
         };
 
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let description = description.map_or("", |s| s.body).to_string();
+        let dod = dod.map_or("", |s| s.body).to_string();
+
+        let extra_sections = schema
+            .extra_sections
+            .iter()
+            .filter_map(|heading| {
+                let body = find_section(&sections, &[normalize_title(heading)])?;
+                (!body.is_empty()).then(|| (heading.clone(), body.to_string()))
+            })
+            .collect();
+
+        let section_headings: Vec<String> = CardSection::ALL
+            .iter()
+            .flat_map(|section| section.headings(schema))
+            .collect();
+        let other_sections = sections
+            .iter()
+            .filter(|section| !section_headings.contains(&normalize_title(&section.title)))
+            .map(|section| (section.heading.to_string(), section.body.to_string()))
+            .collect();
+
         Ok(PldCard {
+            key: card_resp
+                .id
+                .clone()
+                .or_else(|| card_resp.url.clone())
+                .unwrap_or_else(|| card_resp.name.trim().to_string()),
+            number: None,
             name: card_resp.name.clone(),
             section: card_resp.section.clone(),
             sub_section: card_resp.sub_section.clone(),
-            wish,
+            wishes,
+            wish_context,
             description,
+            dod_items: DodItem::from_markdown(&dod),
             dod,
+            extra_sections,
+            preamble: preamble(&content, &known).to_string(),
+            other_sections,
+            section_order: sections.iter().map(|s| s.title.clone()).collect(),
+            user_wish: wish.map_or("", |s| s.body).to_string(),
             working_days: card_resp.working_days,
-            assignees: Self::map_assignees(card_resp.assignees.clone()),
+            assignees: card_resp
+                .assignees
+                .iter()
+                .map(|login| card_resp.assignee_names.get(login).unwrap_or(login).clone())
+                .collect(),
             status: card_resp.status.clone(),
+            fields: card_resp.fields.clone(),
+            references: card_resp.references.clone(),
+            dependencies: card_resp
+                .dependencies
+                .iter()
+                .cloned()
+                .chain(
+                    dependency_references(&content, schema)
+                        .into_iter()
+                        .map(|reference| {
+                            match (&card_resp.repository, reference.strip_prefix('#')) {
+                                // Short issue references point to the repository of the card
+                                (Some(repository), Some(number)) => {
+                                    format!("{}#{}", repository, number)
+                                }
+                                _ => reference,
+                            }
+                        }),
+                )
+                .collect(),
+            linked_issues: card_resp.linked_issues.clone(),
+            depends_on: Vec::new(),
         })
     }
 
-    fn map_assignees(a: Vec<String>) -> Vec<String> {
-        a.iter()
-            .map(|e| {
-                match e.as_str() {
-                    "Nydragon" => "Nicolas Lattemann",
-                    "mindoodoo" => "Léon Sautour",
-                    "limeal" => "Paul Gazeau-Rousseau",
-                    "RaizorZawarudo" => "Mickeal Reiss",
-                    "theCaptain136" => "Pablo Herrmann",
-                    _ => e,
+    /// Title of the card preceded by its number, if it has one
+    pub fn numbered_name(&self) -> String {
+        match &self.number {
+            Some(number) => format!("{} {}", number, self.name.trim()),
+            None => self.name.trim().to_string(),
+        }
+    }
+
+    /// Identifier of the anchor written before the card in the PLD, the key keeps apart the
+    /// cards sharing a title
+    pub fn anchor(&self) -> String {
+        anchor(&self.name, &self.key)
+    }
+
+    /// Links to the cards this one depends on
+    fn dependency_links(&self) -> String {
+        self.depends_on
+            .iter()
+            .map(|(title, anchor)| format!("[{}](#{})", title, anchor))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Share of the definition of done items met
+    pub fn completion(&self) -> Completion {
+        self.dod_items.iter().map(DodItem::completion).sum()
+    }
+
+    /// Renders the card using a user provided template
+    ///
+    /// Supported tags are `{{name}}`, `{{number}}`, `{{status}}`, `{{user}}`, `{{action}}` and
+    /// `{{benefit}}` of the first wish, `{{wishes}}`, `{{description}}`, `{{dod}}`,
+    /// `{{dod_completion}}`, `{{dependencies}}`, `{{working_days}}`, `{{assignees}}`,
+    /// `{{field:<field name>}}` for custom project fields and `{{section:<heading>}}` for extra
+    /// sections of the card schema. Fields and sections missing from the card are replaced by an
+    /// empty string.
+    pub fn render(&self, template: &str) -> String {
+        let first_wish = self.wishes.first().cloned().unwrap_or_default();
+
+        replace_tags(template, |tag| {
+            let value = match tag {
+                "name" => self.name.trim().to_string(),
+                "number" => self.number.clone().unwrap_or_default(),
+                "status" => self.status.clone(),
+                "user" => first_wish.user.clone(),
+                "action" => first_wish.action.clone(),
+                "benefit" => first_wish.benefit.clone().unwrap_or_default(),
+                "wishes" => self.wishes_markdown(WISH_MARKERS),
+                "description" => self.description.clone(),
+                "dod" => self.dod.clone(),
+                "dod_completion" => self.completion().to_string(),
+                "dependencies" => self.dependency_links(),
+                "working_days" => self.working_days.to_string(),
+                "assignees" => self.assignees.join(", "),
+                _ => {
+                    if let Some(name) = tag.strip_prefix("field:") {
+                        self.fields
+                            .get(name)
+                            .map(|v| v.to_string())
+                            .unwrap_or_default()
+                    } else if let Some(name) = tag.strip_prefix("section:") {
+                        self.extra_sections
+                            .iter()
+                            .find(|(heading, _)| heading == name)
+                            .map(|(_, body)| body.clone())
+                            .unwrap_or_default()
+                    } else {
+                        return None;
+                    }
                 }
-                .into()
-            })
-            .collect()
+            };
+
+            Some(value)
+        })
+    }
+
+    /// Every wish of the card, one paragraph per clause introduced by the user, action and
+    /// benefit markers, after the text written before the first wish
+    fn wishes_markdown(&self, [user, action, benefit]: [&str; 3]) -> String {
+        let context = (!self.wish_context.is_empty()).then(|| self.wish_context.clone());
+
+        context
+            .into_iter()
+            .chain(self.wishes.iter().map(|wish| {
+                let mut clauses = vec![
+                    format!("**{}** {}", user, wish.user),
+                    format!("**{}** {}", action, wish.action),
+                ];
+                clauses.extend(
+                    wish.benefit
+                        .iter()
+                        .map(|b| format!("**{}** {}", benefit, b)),
+                );
+
+                clauses.join("\n\n")
+            }))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Anchor identifier of a card, ie: `card-2-1-api-cc11ed80` for `2.1 API` keyed by its title,
+/// ending with a hash of the card key which is the same across runs
+fn anchor(title: &str, key: &str) -> String {
+    let mut anchor = String::from("card");
+
+    for word in title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        anchor.push('-');
+        anchor.push_str(&word.to_lowercase());
+    }
+
+    // FNV-1a, the hashers of the standard library may change between releases
+    let hash = key.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+
+    format!("{}-{:08x}", anchor, hash)
+}
+
+/// Replaces every `{{tag}}` of the template with the value `lookup` gives, unknown tags are kept
+///
+/// The template is scanned once, so tags within the substituted values, which come from the card
+/// bodies, are never expanded.
+fn replace_tags(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    const TAG_START: &str = "{{";
+    const TAG_END: &str = "}}";
+
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find(TAG_START) {
+        let Some(len) = rest[start..].find(TAG_END) else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+
+        match lookup(&rest[start + TAG_START.len()..start + len]) {
+            Some(value) => {
+                output.push_str(&value);
+                rest = &rest[start + len + TAG_END.len()..];
+            }
+            // Skip only the opening braces, in case a tag starts within them
+            None => {
+                output.push_str(TAG_START);
+                rest = &rest[start + TAG_START.len()..];
+            }
+        }
     }
+
+    output.push_str(rest);
+    output
 }
 
 impl fmt::Display for PldCard {
@@ -152,15 +481,40 @@ impl fmt::Display for PldCard {
             self.status
         );
 
-        write!(f, "{} {status}\n\n", &self.name.trim())?;
-        write!(f, "**As a:** {}\n\n", self.wish.user)?;
-        write!(f, "**I want to:** {}\n\n", self.wish.action)?;
+        write!(f, "{} {status}\n\n", self.numbered_name())?;
+        // Optional sections missing from the card are left out
+        if !self.wishes.is_empty() {
+            write!(f, "{}\n\n", self.wishes_markdown(WISH_MARKERS))?;
+        }
+
+        for (heading, body) in [
+            ("Description", &self.description),
+            ("Definition of Done", &self.dod),
+        ]
+        .into_iter()
+        .chain(self.extra_sections.iter().map(|(h, b)| (h.as_str(), b)))
+        {
+            if !body.is_empty() {
+                write!(f, "**{}**\n\n{}\n\n", heading, body)?;
+            }
+        }
+
+        let completion = self.completion();
+        if completion.total > 0 {
+            write!(f, "**DOD completion :** {}\n\n", completion)?;
+        }
+
+        if !self.depends_on.is_empty() {
+            write!(f, "**Depends on :** {}\n\n", self.dependency_links())?;
+        }
 
-        write!(f, "**Description**\n\n{}\n\n", &self.description)?;
-        write!(f, "**Definition of Done**\n\n{}\n\n", self.dod)?;
         write!(f, "**Working days :** {}\n\n", self.working_days)?;
         write!(f, "**Assignees :** {}", self.assignees.join(", "))?;
 
+        for (name, value) in &self.fields {
+            write!(f, "\n\n**{} :** {}", name, value)?;
+        }
+
         Ok(())
     }
 }
@@ -183,3 +537,72 @@ pub fn sort_by_section(
 
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::card::fixtures;
+
+    /// Body writing tags in the card text, which must be kept as is
+    const BODY: &str =
+        "# User wish\n\n**As the:** user\n\n**I want to:** read {{field:Secret}}\n\n\
+                        # Description\n\nMentions {{dod}} and {{section:Risks}}\n\n\
+                        # DOD\n\n- [x] done\n\n# Risks\n\nSee {{section:Risks}}";
+
+    fn card() -> PldCard {
+        let schema = CardSchema {
+            extra_sections: vec!["Risks".to_string()],
+            ..CardSchema::default()
+        };
+        let card = ProjectCard {
+            fields: BTreeMap::from([
+                ("Priority".to_string(), FieldValue::Text("High".to_string())),
+                ("Secret".to_string(), FieldValue::Text("leaked".to_string())),
+            ]),
+            ..fixtures::card("1.1 Login", BODY)
+        };
+
+        PldCard::new(&card, &schema).unwrap()
+    }
+
+    #[test]
+    fn tags_are_replaced() {
+        let output = card().render("{{name}} ({{field:Priority}}) {{dod_completion}} {{unknown}}");
+
+        assert_eq!(output, "1.1 Login (High) 1/1 (100%) {{unknown}}");
+    }
+
+    #[test]
+    fn tags_within_values_are_not_expanded() {
+        let output = card().render("{{action}}|{{description}}|{{section:Risks}}");
+
+        assert_eq!(
+            output,
+            "read {{field:Secret}}|Mentions {{dod}} and {{section:Risks}}|See {{section:Risks}}"
+        );
+    }
+
+    #[test]
+    fn missing_fields_and_sections_are_empty() {
+        let output = card().render("[{{field:Sprint}}][{{section:Notes}}]{{{{name}}");
+
+        assert_eq!(output, "[][]{{1.1 Login");
+    }
+
+    #[test]
+    fn anchors_tell_apart_cards_sharing_a_title() {
+        let mut first = card();
+        first.key = "PVTI_1".to_string();
+        let mut second = card();
+        second.key = "PVTI_2".to_string();
+        first.depends_on = vec![("1.1 Login".to_string(), second.anchor())];
+
+        assert_ne!(first.anchor(), second.anchor());
+        assert!(first.anchor().starts_with("card-1-1-login-"));
+        assert_eq!(anchor("2.1 API", "2.1 API"), "card-2-1-api-cc11ed80");
+        assert_eq!(
+            first.render("{{dependencies}}"),
+            format!("[1.1 Login](#{})", second.anchor())
+        );
+    }
+}
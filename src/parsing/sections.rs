@@ -0,0 +1,231 @@
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+/// Part of a card body introduced by a heading
+#[derive(Debug)]
+pub struct Section<'a> {
+    /// Heading text without the markdown markers
+    pub title: String,
+    /// Heading as written in the card body, markers and inline markup included
+    pub heading: &'a str,
+    /// Byte offset of the heading in the card body
+    pub offset: usize,
+    /// Markdown between the heading and the next section, sub-headings and code blocks included
+    pub body: &'a str,
+    pub body_offset: usize,
+    /// Headings nested under the section heading, kept in its body
+    pub sub_headings: Vec<SubHeading>,
+}
+
+#[derive(Debug)]
+pub struct SubHeading {
+    pub title: String,
+    pub offset: usize,
+}
+
+struct Heading {
+    title: String,
+    level: HeadingLevel,
+    range: Range<usize>,
+}
+
+/// Normalizes a heading title so `## dod :` and `# DOD` compare equal
+pub fn normalize_title(title: &str) -> String {
+    title.trim().trim_end_matches(':').trim().to_lowercase()
+}
+
+/// Lists the headings of a markdown document, headings inside code blocks are not included
+///
+/// Lines such as `#DOD` are not headings for CommonMark, which requires a space after the `#`,
+/// but they are still accepted when their title is one of the `known` ones.
+fn headings(content: &str, known: &[&str]) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut current: Option<Heading> = None;
+
+    for (event, range) in Parser::new(content).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Paragraph) => {
+                headings.extend(unspaced_headings(content, range, known));
+            }
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some(Heading {
+                    title: String::new(),
+                    level,
+                    range,
+                })
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = current.as_mut() {
+                    heading.title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => headings.extend(current.take()),
+            _ => (),
+        }
+    }
+
+    headings
+}
+
+/// Lines of a paragraph written as a known heading without a space after the `#`
+fn unspaced_headings<'a>(
+    content: &'a str,
+    paragraph: Range<usize>,
+    known: &'a [&str],
+) -> impl Iterator<Item = Heading> + 'a {
+    let mut start = paragraph.start;
+
+    content[paragraph]
+        .split_inclusive('\n')
+        .filter_map(move |line| {
+            let line_start = start;
+            start += line.len();
+
+            let level = line.chars().take_while(|c| *c == '#').count();
+            let title = line[level..].trim_end();
+            let at_line_start = line_start == 0 || content[..line_start].ends_with('\n');
+
+            let unspaced = (1..=6).contains(&level)
+                && at_line_start
+                && title.starts_with(|c: char| !c.is_whitespace());
+            (unspaced && known.contains(&normalize_title(title).as_str())).then(|| Heading {
+                title: title.to_string(),
+                level: HeadingLevel::try_from(level).unwrap(),
+                range: line_start..line_start + line.trim_end().len(),
+            })
+        })
+}
+
+/// Lines of a markdown document along with whether they belong to a code block, fenced or
+/// indented, as markdown renderers see them
+pub fn code_lines(content: &str) -> impl Iterator<Item = (&str, bool)> {
+    let code_blocks: Vec<Range<usize>> = Parser::new(content)
+        .into_offset_iter()
+        .filter_map(|(event, range)| {
+            matches!(event, Event::Start(Tag::CodeBlock(_))).then_some(range)
+        })
+        .collect();
+
+    let mut start = 0;
+    content.split('\n').map(move |line| {
+        let line_start = start;
+        start += line.len() + 1;
+
+        let in_code = code_blocks
+            .iter()
+            .any(|block| block.start <= line_start + line.len() && line_start < block.end);
+        (line, in_code)
+    })
+}
+
+/// Lines of a markdown document outside of code blocks
+pub fn lines_outside_code(content: &str) -> impl Iterator<Item = &str> {
+    code_lines(content).filter_map(|(line, in_code)| (!in_code).then_some(line))
+}
+
+/// Text before the first heading of a markdown document
+pub fn preamble<'a>(content: &'a str, known: &[&str]) -> &'a str {
+    let end = headings(content, known)
+        .first()
+        .map_or(content.len(), |heading| heading.range.start);
+
+    content[..end].trim()
+}
+
+/// Splits a card body into sections
+///
+/// Every heading whose normalized title is in `known` starts a new section, whatever its level.
+/// Other headings start a new section only when they are not nested under the current one,
+/// otherwise they are kept in its body. Anything before the first heading is ignored.
+pub fn split_sections<'a>(content: &'a str, known: &[&str]) -> Vec<Section<'a>> {
+    let mut starts: Vec<(Heading, Vec<SubHeading>)> = Vec::new();
+
+    for heading in headings(content, known) {
+        let is_known = known.contains(&normalize_title(&heading.title).as_str());
+
+        match starts.last_mut() {
+            Some((last, sub_headings)) if !is_known && heading.level > last.level => sub_headings
+                .push(SubHeading {
+                    title: heading.title.trim().to_string(),
+                    offset: heading.range.start,
+                }),
+            _ => starts.push((heading, Vec::new())),
+        }
+    }
+
+    let ends: Vec<usize> = starts
+        .iter()
+        .skip(1)
+        .map(|(heading, _)| heading.range.start)
+        .chain([content.len()])
+        .collect();
+
+    starts
+        .into_iter()
+        .zip(ends)
+        .map(|((heading, sub_headings), end)| {
+            let body = trim_blank_lines(content, heading.range.end..end);
+
+            Section {
+                title: heading.title.trim().to_string(),
+                heading: content[heading.range.clone()].trim_end(),
+                offset: heading.range.start,
+                body: &content[body.clone()],
+                body_offset: body.start,
+                sub_headings,
+            }
+        })
+        .collect()
+}
+
+/// Narrows `range` to exclude the surrounding blank lines while keeping the indentation of the
+/// first line
+fn trim_blank_lines(content: &str, range: Range<usize>) -> Range<usize> {
+    let text = content[range.clone()].trim_end();
+    let start = text
+        .char_indices()
+        .take_while(|(_, c)| c.is_whitespace())
+        .filter(|(_, c)| *c == '\n')
+        .last()
+        .map_or(0, |(i, _)| i + 1);
+
+    range.start + start.min(text.len())..range.start + text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: [&str; 3] = ["user wish", "description", "dod"];
+
+    #[test]
+    fn headings_without_space_are_accepted_for_known_titles() {
+        let content =
+            "#User wish\n**As the:** user\n\n#Description\nText #hashtag\n#tag\n\n##DOD:\n- done";
+
+        let sections = split_sections(content, &KNOWN);
+
+        let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["User wish", "Description", "DOD:"]);
+        assert_eq!(sections[0].body, "**As the:** user");
+        assert_eq!(sections[1].body, "Text #hashtag\n#tag");
+        assert_eq!(sections[2].body, "- done");
+    }
+
+    #[test]
+    fn headings_inside_code_blocks_are_ignored() {
+        let content = "# Description\n\n```\n#DOD\n# DOD\n```\n\n    #DOD";
+
+        let sections = split_sections(content, &KNOWN);
+
+        assert_eq!(sections.len(), 1);
+        assert!(sections[0].body.ends_with("    #DOD"));
+    }
+
+    #[test]
+    fn preamble_stops_at_the_first_heading() {
+        assert_eq!(preamble("Context\n#User wish\n", &KNOWN), "Context");
+        assert_eq!(preamble("Context\n# Notes\n", &KNOWN), "Context");
+        assert_eq!(preamble("Context only", &KNOWN), "Context only");
+    }
+}
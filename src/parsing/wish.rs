@@ -0,0 +1,288 @@
+use regress::Regex;
+
+use super::FLAGS;
+use crate::config::CardSchema;
+
+/// This is the first part of the card
+///
+/// > As the `user`, I want to `action` so that `benefit`
+#[derive(Debug, Default, Clone)]
+pub struct UserWish {
+    pub user: String,
+    pub action: String,
+    pub benefit: Option<String>,
+}
+
+/// Clause of a wish introduced by one of the markers of the schema
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Clause {
+    User,
+    Action,
+    Benefit,
+}
+
+/// Escapes the regex syntax characters of a literal
+fn escape_regex(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| {
+            let escape = "\\^$.|?*+()[]{}/".contains(c).then_some('\\');
+            escape.into_iter().chain([c])
+        })
+        .collect()
+}
+
+/// Pattern matching any of the markers, ignoring their trailing colon and the width of spaces
+pub(super) fn markers_pattern(markers: &[String]) -> String {
+    let patterns: Vec<String> = markers
+        .iter()
+        .map(|marker| {
+            marker
+                .trim()
+                .trim_end_matches(':')
+                .split_whitespace()
+                .map(escape_regex)
+                .collect::<Vec<_>>()
+                .join(r"\s+")
+        })
+        .filter(|pattern| !pattern.is_empty())
+        .collect();
+
+    if patterns.is_empty() {
+        // Never matches
+        "(?!)".to_string()
+    } else {
+        patterns.join("|")
+    }
+}
+
+/// Regex matching any marker of the schema, capturing the user, action and benefit markers in
+/// groups 2 to 4 and the bold markers around them in groups 1 and 5
+fn marker_regex(schema: &CardSchema) -> Regex {
+    Regex::with_flags(
+        &format!(
+            r"(\*\*\s*)?\b(?:({})|({})|({}))(?!\w)\s*:?\s*(\*\*)?\s*:?",
+            markers_pattern(&schema.user_markers),
+            markers_pattern(&schema.action_markers),
+            markers_pattern(&schema.benefit_markers)
+        ),
+        FLAGS,
+    )
+    .unwrap()
+}
+
+/// Words of a user wish section once its markers are removed, ignoring case and punctuation
+///
+/// Two sections with the same words hold the same wishes, whatever their markers and layout.
+pub(super) fn wish_words(user_wish: &str, schema: &CardSchema) -> Vec<String> {
+    let marker_regex = marker_regex(schema);
+
+    let mut text = String::new();
+    let mut last = 0;
+    for m in marker_regex.find_iter(user_wish) {
+        text.push_str(&user_wish[last..m.start()]);
+        text.push(' ');
+        last = m.end();
+    }
+    text.push_str(&user_wish[last..]);
+
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Trims the text of a clause, along with the separators and bullets announcing the next one
+fn clause_text(text: &str) -> String {
+    let mut lines: Vec<&str> = text.trim().lines().collect();
+    if lines
+        .last()
+        .is_some_and(|line| matches!(line.trim(), "-" | "*" | "+" | ">"))
+    {
+        lines.pop();
+    }
+
+    let text = lines.join("\n");
+    let mut text = text.trim().trim_end_matches(',').trim();
+
+    // Bold markers wrapping the whole sentence, ie: `**As a user**, I want to`
+    if text.matches("**").count() % 2 == 1 {
+        text = text
+            .strip_prefix("**")
+            .or_else(|| text.strip_suffix("**"))
+            .unwrap_or(text)
+            .trim();
+    }
+
+    text.to_string()
+}
+
+impl UserWish {
+    /// Reads every wish of the user wish section along with the text written before the first
+    /// one, `None` if there is no wish or one is incomplete
+    ///
+    /// Markers are matched ignoring case and may be written in bold, ie: `**As the:** user`, or
+    /// within a plain sentence, ie: `As a user, I want to log in so that my data is kept`. Plain
+    /// user markers must start a line, a plain action or benefit marker is the first one
+    /// following the user. The benefit is optional.
+    pub fn from_markdown(user_wish: &str, schema: &CardSchema) -> Option<(String, Vec<UserWish>)> {
+        let marker_regex = marker_regex(schema);
+
+        // Clauses in order of appearance, with whether their marker is bold and its range
+        let mut markers = Vec::new();
+        for m in marker_regex.find_iter(user_wish) {
+            let clause = if m.group(2).is_some() {
+                Clause::User
+            } else if m.group(3).is_some() {
+                Clause::Action
+            } else {
+                Clause::Benefit
+            };
+            let bold = m.group(1).is_some() && m.group(5).is_some();
+
+            let accepted = bold
+                || match clause {
+                    Clause::User => {
+                        let line_start = user_wish[..m.start()].rfind('\n').map_or(0, |i| i + 1);
+                        user_wish[line_start..m.start()]
+                            .trim_matches(|c: char| c.is_whitespace() || "-*+>".contains(c))
+                            .is_empty()
+                    }
+                    // Only the first plain marker following a plain previous clause is one, wishes
+                    // written with bold markers may use the phrasing in their text
+                    Clause::Action => markers
+                        .last()
+                        .is_some_and(|(c, b, _)| *c == Clause::User && !b),
+                    Clause::Benefit => markers
+                        .last()
+                        .is_some_and(|(c, b, _)| *c == Clause::Action && !b),
+                };
+
+            if accepted {
+                markers.push((clause, bold, m.range.clone()));
+            }
+        }
+
+        let context = markers
+            .first()
+            .map_or("", |(_, _, range)| &user_wish[..range.start]);
+
+        let ends = markers
+            .iter()
+            .skip(1)
+            .map(|(_, _, range)| range.start)
+            .chain([user_wish.len()]);
+
+        let mut wishes: Vec<UserWish> = Vec::new();
+        for ((clause, _, range), end) in markers.iter().zip(ends) {
+            let text = clause_text(&user_wish[range.end..end]);
+
+            match clause {
+                Clause::User => wishes.push(UserWish {
+                    user: text,
+                    ..UserWish::default()
+                }),
+                Clause::Action => {
+                    let wish = wishes.last_mut()?;
+                    if !wish.action.is_empty() {
+                        return None;
+                    }
+                    wish.action = text;
+                }
+                Clause::Benefit => {
+                    let wish = wishes.last_mut()?;
+                    if wish.benefit.is_some() {
+                        return None;
+                    }
+                    wish.benefit = Some(text).filter(|text| !text.is_empty());
+                }
+            }
+        }
+
+        let complete = wishes
+            .iter()
+            .all(|wish| !wish.user.is_empty() && !wish.action.is_empty());
+
+        (!wishes.is_empty() && complete).then(|| (clause_text(context), wishes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(user_wish: &str) -> Option<(String, Vec<UserWish>)> {
+        UserWish::from_markdown(user_wish, &CardSchema::default())
+    }
+
+    fn clauses(wish: &UserWish) -> (&str, &str, Option<&str>) {
+        (&wish.user, &wish.action, wish.benefit.as_deref())
+    }
+
+    #[test]
+    fn plain_sentences_are_read() {
+        let (context, wishes) =
+            read("As a user, I want to log in so that my data is kept").unwrap();
+
+        assert_eq!(context, "");
+        assert_eq!(
+            clauses(&wishes[0]),
+            ("user", "log in", Some("my data is kept"))
+        );
+    }
+
+    #[test]
+    fn french_markers_are_read() {
+        let (_, wishes) = read(
+            "**En tant que :** utilisateur\n\n**Je veux :** me connecter\n\n\
+             **Afin que :** mes données soient gardées",
+        )
+        .unwrap();
+
+        assert_eq!(
+            clauses(&wishes[0]),
+            (
+                "utilisateur",
+                "me connecter",
+                Some("mes données soient gardées")
+            )
+        );
+    }
+
+    #[test]
+    fn the_benefit_is_optional() {
+        let (_, wishes) = read("**As the:** user\n**I want to:** log in").unwrap();
+        assert_eq!(clauses(&wishes[0]), ("user", "log in", None));
+
+        let (_, wishes) = read("**As the:** user\n**I want to:** log in\n**So that:**").unwrap();
+        assert_eq!(clauses(&wishes[0]), ("user", "log in", None));
+    }
+
+    #[test]
+    fn several_wishes_are_read() {
+        let (_, wishes) = read(
+            "- As an admin, I want to ban users\n\
+             - **As the:** user **I want to:** log in **So that:** I can post",
+        )
+        .unwrap();
+
+        assert_eq!(wishes.len(), 2);
+        assert_eq!(clauses(&wishes[0]), ("admin", "ban users", None));
+        assert_eq!(clauses(&wishes[1]), ("user", "log in", Some("I can post")));
+    }
+
+    #[test]
+    fn text_before_the_first_wish_is_returned() {
+        let (context, wishes) =
+            read("Context: the team.\n\n**As the:** user\n**I want to:** log in").unwrap();
+
+        assert_eq!(context, "Context: the team.");
+        assert_eq!(clauses(&wishes[0]), ("user", "log in", None));
+    }
+
+    #[test]
+    fn incomplete_wishes_are_rejected() {
+        assert!(read("Some text").is_none());
+        assert!(read("**As the:** user").is_none());
+        assert!(read("**As the:** user\n**I want to:** a\n**I want to:** b").is_none());
+    }
+}
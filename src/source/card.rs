@@ -0,0 +1,110 @@
+use std::{collections::BTreeMap, fmt};
+
+/// Value of a custom project field
+#[derive(Debug, Clone)]
+pub enum FieldValue {
+    Text(String),
+    Number(f64),
+    Date(String),
+    SingleSelect(String),
+    Iteration(String),
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Text(text) => write!(f, "{}", text),
+            FieldValue::Number(number) => write!(f, "{}", number),
+            FieldValue::Date(date) => write!(f, "{}", date),
+            FieldValue::SingleSelect(name) => write!(f, "{}", name),
+            FieldValue::Iteration(title) => write!(f, "{}", title),
+        }
+    }
+}
+
+/// Kind of item a card comes from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ItemType {
+    /// Draft issue, or card not backed by any tracker such as markdown or csv cards
+    #[default]
+    Draft,
+    Issue,
+    PullRequest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueState {
+    Open,
+    Closed,
+}
+
+/// Structure representing one card, as provided by any card source
+#[derive(Debug, Default)]
+pub struct ProjectCard {
+    pub name: String,
+    pub content: String,
+    pub section: String,
+    pub sub_section: String,
+    pub working_days: f32,
+    pub status: String,
+    /// Logins, or names for sources without logins
+    pub assignees: Vec<String>,
+    /// Display names of the assignees given by the source, indexed by login
+    pub assignee_names: BTreeMap<String, String>,
+    /// Every custom field value of the card indexed by field name
+    pub fields: BTreeMap<String, FieldValue>,
+    pub labels: Vec<String>,
+    /// State of the underlying issue, `None` for drafts
+    pub state: Option<IssueState>,
+    pub item_type: ItemType,
+    pub archived: bool,
+    /// Link to the card, or the file it was read from for local sources
+    pub url: Option<String>,
+    /// Identifier of the card content in its source, kept across runs and used to write changes
+    /// back
+    pub id: Option<String>,
+    /// Short references other cards may use to point to this one besides its title, ie: `#12`
+    pub references: Vec<String>,
+    /// Repository of the card as `owner/repo`, for sources spanning several repositories, `#12`
+    /// references written in the card body point to an issue of this repository
+    pub repository: Option<String>,
+    /// References of the cards this one depends on, as given by the card metadata
+    pub dependencies: Vec<String>,
+    /// Links to the issues this one depends on, as given by the relationships of the source, ie:
+    /// github sub-issues, which may be outside of the PLD
+    pub linked_issues: Vec<String>,
+}
+
+impl ProjectCard {
+    /// Names of the card information left empty by the source
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        [
+            ("Section", self.section.is_empty()),
+            ("Sub-section", self.sub_section.is_empty()),
+            ("Status", self.status.is_empty()),
+            ("Working days", self.working_days == 0.0),
+        ]
+        .into_iter()
+        .filter_map(|(name, missing)| missing.then_some(name))
+        .collect()
+    }
+}
+
+/// Cards shared by the tests of every module
+#[cfg(test)]
+pub mod fixtures {
+    use super::ProjectCard;
+
+    /// Body holding every required section of the default card schema
+    pub const BODY: &str = "# User wish\n\n**As the:** user\n\n**I want to:** log in\n\n\
+                            # Description\n\nText\n\n# DOD\n\n- done";
+
+    /// Card with the given name and body, every other information left empty
+    pub fn card(name: &str, content: &str) -> ProjectCard {
+        ProjectCard {
+            name: name.to_string(),
+            content: content.to_string(),
+            ..ProjectCard::default()
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use async_trait::async_trait;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+use super::{
+    card::{FieldValue, ItemType, ProjectCard},
+    CardSource, SourceError,
+};
+use crate::config::{CsvColumns, CsvConfig};
+
+#[derive(Debug)]
+pub enum CsvError {
+    /// The file could not be read or is not valid CSV
+    Read(PathBuf, csv::Error),
+    /// A mandatory column is missing from the header row
+    MissingColumn(String),
+    /// The working days cell of a row is not a number, `line` is where the row starts in the file
+    InvalidWorkingDays { line: usize, value: String },
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Read(path, e) => write!(f, "Reading {} failed : {}", path.display(), e),
+            CsvError::MissingColumn(column) => {
+                write!(f, "Column \"{}\" is missing from the CSV header", column)
+            }
+            CsvError::InvalidWorkingDays { line, value } => write!(
+                f,
+                "Row at line {} has invalid working days \"{}\", expected a number",
+                line, value
+            ),
+        }
+    }
+}
+
+impl Error for CsvError {}
+
+/// Card source reading one card per row of a CSV file
+///
+/// The card body is either taken as is from a single column, or assembled in the card format
+/// from separate user, action, description and DOD columns.
+pub struct CsvSource {
+    path: PathBuf,
+    delimiter: u8,
+    columns: CsvColumns,
+}
+
+/// Index of each configured column in the header row
+struct ColumnIndexes {
+    name: usize,
+    id: Option<usize>,
+    body: Option<usize>,
+    user: Option<usize>,
+    action: Option<usize>,
+    description: Option<usize>,
+    dod: Option<usize>,
+    section: Option<usize>,
+    sub_section: Option<usize>,
+    status: Option<usize>,
+    working_days: Option<usize>,
+    assignees: Option<usize>,
+}
+
+impl CsvSource {
+    pub fn new(conf: &CsvConfig) -> CsvSource {
+        CsvSource {
+            path: PathBuf::from(&conf.path),
+            delimiter: conf.delimiter as u8,
+            columns: conf.columns.clone(),
+        }
+    }
+
+    fn column_indexes(&self, headers: &csv::StringRecord) -> Result<ColumnIndexes, CsvError> {
+        let find = |column: &str| headers.iter().position(|h| h.trim() == column);
+        let find_opt = |column: &Option<String>| column.as_deref().and_then(find);
+
+        let indexes = ColumnIndexes {
+            name: find(&self.columns.name)
+                .ok_or_else(|| CsvError::MissingColumn(self.columns.name.clone()))?,
+            id: match &self.columns.id {
+                Some(column) => {
+                    Some(find(column).ok_or_else(|| CsvError::MissingColumn(column.clone()))?)
+                }
+                None => None,
+            },
+            body: find_opt(&self.columns.body),
+            user: find(&self.columns.user),
+            action: find(&self.columns.action),
+            description: find(&self.columns.description),
+            dod: find(&self.columns.dod),
+            section: find(&self.columns.section),
+            sub_section: find(&self.columns.sub_section),
+            status: find(&self.columns.status),
+            working_days: find(&self.columns.working_days),
+            assignees: find(&self.columns.assignees),
+        };
+
+        if indexes.body.is_none() {
+            let body_columns = [
+                (&self.columns.user, indexes.user),
+                (&self.columns.action, indexes.action),
+                (&self.columns.description, indexes.description),
+                (&self.columns.dod, indexes.dod),
+            ];
+
+            if let Some((column, _)) = body_columns.iter().find(|(_, index)| index.is_none()) {
+                return Err(CsvError::MissingColumn(column.to_string()));
+            }
+        }
+
+        Ok(indexes)
+    }
+
+    fn read_cards(&self) -> Result<Vec<ProjectCard>, CsvError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .flexible(true)
+            .from_path(&self.path)
+            .map_err(|e| CsvError::Read(self.path.clone(), e))?;
+
+        let headers = reader
+            .headers()
+            .map_err(|e| CsvError::Read(self.path.clone(), e))?
+            .clone();
+        let indexes = self.column_indexes(&headers)?;
+
+        let known_columns = [
+            Some(indexes.name),
+            indexes.id,
+            indexes.body,
+            indexes.user,
+            indexes.action,
+            indexes.description,
+            indexes.dod,
+            indexes.section,
+            indexes.sub_section,
+            indexes.status,
+            indexes.working_days,
+            indexes.assignees,
+        ];
+
+        let mut cards = Vec::new();
+
+        for record in reader.records() {
+            let record = record.map_err(|e| CsvError::Read(self.path.clone(), e))?;
+            // Quoted cells may span several lines, which is common for the DOD column
+            let line = record.position().map_or(0, |p| p.line() as usize);
+            let cell = |index: Option<usize>| {
+                index
+                    .and_then(|i| record.get(i))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            };
+
+            let content = match indexes.body {
+                Some(body) => cell(Some(body)),
+                None => format!(
+                    "# User wish\n\n**As the:** {}\n\n**I want to:** {}\n\n# Description\n\n{}\n\n# DOD\n\n{}",
+                    cell(indexes.user),
+                    cell(indexes.action),
+                    cell(indexes.description),
+                    cell(indexes.dod)
+                ),
+            };
+
+            let working_days = cell(indexes.working_days);
+            let working_days = if working_days.is_empty() {
+                0.0
+            } else {
+                // Spreadsheets in french locales use a decimal comma
+                working_days.replace(',', ".").parse().map_err(|_| {
+                    CsvError::InvalidWorkingDays {
+                        line,
+                        value: working_days.clone(),
+                    }
+                })?
+            };
+
+            // Every other column is exposed as a custom field
+            let fields: BTreeMap<String, FieldValue> = headers
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !known_columns.contains(&Some(*index)))
+                .filter_map(|(index, header)| {
+                    let value = record.get(index)?.trim();
+                    (!value.is_empty()).then(|| {
+                        (
+                            header.trim().to_string(),
+                            FieldValue::Text(value.to_string()),
+                        )
+                    })
+                })
+                .collect();
+
+            cards.push(ProjectCard {
+                name: cell(Some(indexes.name)),
+                content,
+                section: cell(indexes.section),
+                sub_section: cell(indexes.sub_section),
+                working_days,
+                status: cell(indexes.status),
+                assignees: cell(indexes.assignees)
+                    .split([',', ';'])
+                    .map(|a| a.trim().to_string())
+                    .filter(|a| !a.is_empty())
+                    .collect(),
+                assignee_names: BTreeMap::new(),
+                fields,
+                labels: Vec::new(),
+                state: None,
+                item_type: ItemType::Draft,
+                archived: false,
+                url: Some(format!("{} line {}", self.path.display(), line)),
+                // Rows move around the file, the identifier of the card keeps its number
+                id: Some(cell(indexes.id))
+                    .filter(|id| !id.is_empty())
+                    .or_else(|| Some(cell(Some(indexes.name)))),
+                references: Vec::new(),
+                repository: None,
+                dependencies: Vec::new(),
+                linked_issues: Vec::new(),
+            });
+        }
+
+        Ok(cards)
+    }
+}
+
+#[async_trait]
+impl CardSource for CsvSource {
+    fn name(&self) -> String {
+        format!("csv file {}", self.path.display())
+    }
+
+    async fn get_cards(&self) -> Result<Vec<ProjectCard>, SourceError> {
+        self.read_cards().map_err(SourceError::Csv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(file: &str, delimiter: &str) -> CsvSource {
+        let conf: CsvConfig = toml::from_str(&format!(
+            "path = \"{}/tests/fixtures/csv/{}\"\ndelimiter = \"{}\"",
+            env!("CARGO_MANIFEST_DIR"),
+            file,
+            delimiter
+        ))
+        .unwrap();
+
+        CsvSource::new(&conf)
+    }
+
+    #[test]
+    fn non_ascii_delimiters_are_rejected() {
+        let conf = toml::from_str::<CsvConfig>("path = \"cards.csv\"\ndelimiter = \"§\"");
+
+        assert!(conf
+            .unwrap_err()
+            .to_string()
+            .contains("not an ASCII character"));
+    }
+
+    #[test]
+    fn cards_are_assembled_from_the_columns() {
+        let cards = source("cards.csv", ";").read_cards().unwrap();
+
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].name, "1.1 Login");
+        assert_eq!(cards[0].working_days, 1.5);
+        assert!(cards[0]
+            .content
+            .contains("# DOD\n\n- [x] form\n- [ ] tests"));
+        assert!(matches!(&cards[0].fields["Priority"], FieldValue::Text(text) if text == "High"));
+        // The DOD cell of the first card spans lines 2 and 3
+        assert!(cards[1]
+            .url
+            .as_deref()
+            .unwrap()
+            .ends_with("cards.csv line 4"));
+    }
+
+    #[test]
+    fn cards_are_identified_by_the_id_column_or_their_name() {
+        let cards = source("cards.csv", ";").read_cards().unwrap();
+        assert_eq!(cards[1].id.as_deref(), Some("1.2 Logout"));
+
+        let mut source = source("cards.csv", ";");
+        source.columns.id = Some("Priority".to_string());
+        let cards = source.read_cards().unwrap();
+
+        assert_eq!(cards[0].id.as_deref(), Some("High"));
+        assert!(!cards[0].fields.contains_key("Priority"));
+        // Rows with an empty id fall back to their name
+        assert_eq!(cards[1].id.as_deref(), Some("1.2 Logout"));
+
+        source.columns.id = Some("Key".to_string());
+        assert!(matches!(
+            source.read_cards(),
+            Err(CsvError::MissingColumn(column)) if column == "Key"
+        ));
+    }
+
+    #[test]
+    fn rows_spanning_several_lines_report_their_first_line() {
+        let error = source("invalid_days.csv", ";").read_cards().unwrap_err();
+
+        assert!(matches!(
+            error,
+            CsvError::InvalidWorkingDays { line: 4, ref value } if value == "two"
+        ));
+    }
+}
@@ -0,0 +1,522 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use super::card::{FieldValue, IssueState, ItemType, ProjectCard};
+
+#[derive(Debug)]
+pub enum FilterError {
+    /// The expression ended while `expected` was still missing
+    UnexpectedEnd { expected: &'static str },
+    /// A token at the given byte position does not fit the grammar
+    UnexpectedToken {
+        position: usize,
+        found: String,
+        expected: &'static str,
+    },
+    /// A quoted value opened at the given byte position is never closed
+    UnterminatedString(usize),
+}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::UnexpectedEnd { expected } => {
+                write!(f, "Filter ended unexpectedly, expected {}", expected)
+            }
+            FilterError::UnexpectedToken {
+                position,
+                found,
+                expected,
+            } => write!(
+                f,
+                "Unexpected \"{}\" at position {} of the filter, expected {}",
+                found, position, expected
+            ),
+            FilterError::UnterminatedString(position) => {
+                write!(
+                    f,
+                    "Quoted value at position {} of the filter is never closed",
+                    position
+                )
+            }
+        }
+    }
+}
+
+impl Error for FilterError {}
+
+/// Card attribute a filter condition applies to
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Status,
+    Section,
+    SubSection,
+    Assignee,
+    Label,
+    /// Value of every iteration field, usually the sprint
+    Iteration,
+    State,
+    Type,
+    Archived,
+    /// Any other custom field, looked up by name
+    Custom(String),
+}
+
+impl Field {
+    fn from_name(name: &str) -> Field {
+        match name.to_lowercase().as_str() {
+            "status" => Field::Status,
+            "section" => Field::Section,
+            "sub_section" | "sub-section" | "subsection" => Field::SubSection,
+            "assignee" | "assignees" => Field::Assignee,
+            "label" | "labels" => Field::Label,
+            "sprint" | "iteration" => Field::Iteration,
+            "state" => Field::State,
+            "type" => Field::Type,
+            "archived" => Field::Archived,
+            _ => Field::Custom(name.to_string()),
+        }
+    }
+
+    /// Every value of the field on the card, a condition matches if any of them does
+    fn values(&self, card: &ProjectCard) -> Vec<String> {
+        match self {
+            Field::Status => vec![card.status.clone()],
+            Field::Section => vec![card.section.clone()],
+            Field::SubSection => vec![card.sub_section.clone()],
+            Field::Assignee => card
+                .assignees
+                .iter()
+                .chain(card.assignee_names.values())
+                .cloned()
+                .collect(),
+            Field::Label => card.labels.clone(),
+            Field::Iteration => card
+                .fields
+                .values()
+                .filter_map(|value| match value {
+                    FieldValue::Iteration(title) => Some(title.clone()),
+                    _ => None,
+                })
+                .collect(),
+            Field::State => match card.state {
+                Some(IssueState::Open) => vec!["open".to_string()],
+                Some(IssueState::Closed) => vec!["closed".to_string()],
+                None => Vec::new(),
+            },
+            Field::Type => vec![match card.item_type {
+                ItemType::Draft => "draft".to_string(),
+                ItemType::Issue => "issue".to_string(),
+                ItemType::PullRequest => "pr".to_string(),
+            }],
+            Field::Archived => vec![card.archived.to_string()],
+            Field::Custom(name) => card
+                .fields
+                .iter()
+                .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Contains,
+}
+
+/// Parsed filter expression selecting the cards written to the PLD
+///
+/// Conditions are written `field == value`, `field != value` or `field ~ value` (contains) and
+/// combined with `and`, `or`, `not` and parentheses. Values are compared ignoring case and must
+/// be quoted when they hold spaces or operators, ie: `sprint == "Sprint 4" and status != "Won't do"`.
+#[derive(Debug, Clone)]
+pub struct Filter(Expression);
+
+impl Filter {
+    pub fn matches(&self, card: &ProjectCard) -> bool {
+        self.0.matches(card)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Condition {
+        field: Field,
+        operator: Operator,
+        value: String,
+    },
+}
+
+impl Expression {
+    fn matches(&self, card: &ProjectCard) -> bool {
+        match self {
+            Expression::And(lhs, rhs) => lhs.matches(card) && rhs.matches(card),
+            Expression::Or(lhs, rhs) => lhs.matches(card) || rhs.matches(card),
+            Expression::Not(inner) => !inner.matches(card),
+            Expression::Condition {
+                field,
+                operator,
+                value,
+            } => {
+                let value = value.to_lowercase();
+                let mut values = field.values(card).into_iter().map(|v| v.to_lowercase());
+
+                match operator {
+                    Operator::Equal => values.any(|v| v == value),
+                    Operator::NotEqual => !values.any(|v| v == value),
+                    Operator::Contains => values.any(|v| v.contains(&value)),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Unquoted word, either a keyword, a field name or a value
+    Word(String),
+    Quoted(String),
+    Operator(Operator),
+    OpenParen,
+    CloseParen,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Quoted(text) => write!(f, "\"{}\"", text),
+            Token::Operator(Operator::Equal) => write!(f, "=="),
+            Token::Operator(Operator::NotEqual) => write!(f, "!="),
+            Token::Operator(Operator::Contains) => write!(f, "~"),
+            Token::OpenParen => write!(f, "("),
+            Token::CloseParen => write!(f, ")"),
+        }
+    }
+}
+
+/// Splits the expression into tokens along with their byte position
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '~' => Token::Operator(Operator::Contains),
+            '=' | '!' => match chars.next() {
+                Some((_, '=')) => Token::Operator(if c == '=' {
+                    Operator::Equal
+                } else {
+                    Operator::NotEqual
+                }),
+                other => {
+                    return Err(FilterError::UnexpectedToken {
+                        position,
+                        found: format!("{}{}", c, other.map(|(_, c)| c).unwrap_or_default()),
+                        expected: "== or !=",
+                    })
+                }
+            },
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, end)) if end == c => break,
+                        Some((_, inner)) => text.push(inner),
+                        None => return Err(FilterError::UnterminatedString(position)),
+                    }
+                }
+                Token::Quoted(text)
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some((_, next)) = chars.peek() {
+                    if next.is_whitespace() || "()~=!\"'".contains(*next) {
+                        break;
+                    }
+                    word.push(*next);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+        };
+
+        tokens.push((position, token));
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, `not` binds tighter than `and` which binds tighter than `or`
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<(usize, Token), FilterError> {
+        let token = self
+            .tokens
+            .get(self.index)
+            .cloned()
+            .ok_or(FilterError::UnexpectedEnd { expected })?;
+        self.index += 1;
+
+        Ok(token)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn or(&mut self) -> Result<Expression, FilterError> {
+        let mut filter = self.and()?;
+        while self.peek_keyword("or") {
+            self.index += 1;
+            filter = Expression::Or(Box::new(filter), Box::new(self.and()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Expression, FilterError> {
+        let mut filter = self.not()?;
+        while self.peek_keyword("and") {
+            self.index += 1;
+            filter = Expression::And(Box::new(filter), Box::new(self.not()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn not(&mut self) -> Result<Expression, FilterError> {
+        if self.peek_keyword("not") {
+            self.index += 1;
+            return Ok(Expression::Not(Box::new(self.not()?)));
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expression, FilterError> {
+        match self.next("a condition")? {
+            (_, Token::OpenParen) => {
+                let filter = self.or()?;
+                match self.next(")")? {
+                    (_, Token::CloseParen) => Ok(filter),
+                    (position, token) => Err(FilterError::UnexpectedToken {
+                        position,
+                        found: token.to_string(),
+                        expected: ")",
+                    }),
+                }
+            }
+            (_, Token::Word(name)) | (_, Token::Quoted(name)) => {
+                let operator = match self.next("an operator")? {
+                    (_, Token::Operator(operator)) => operator,
+                    (position, token) => {
+                        return Err(FilterError::UnexpectedToken {
+                            position,
+                            found: token.to_string(),
+                            expected: "an operator",
+                        })
+                    }
+                };
+                let value = match self.next("a value")? {
+                    (_, Token::Word(value)) | (_, Token::Quoted(value)) => value,
+                    (position, token) => {
+                        return Err(FilterError::UnexpectedToken {
+                            position,
+                            found: token.to_string(),
+                            expected: "a value",
+                        })
+                    }
+                };
+
+                Ok(Expression::Condition {
+                    field: Field::from_name(&name),
+                    operator,
+                    value,
+                })
+            }
+            (position, token) => Err(FilterError::UnexpectedToken {
+                position,
+                found: token.to_string(),
+                expected: "a condition",
+            }),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            index: 0,
+        };
+
+        let filter = parser.or()?;
+        match parser.tokens.get(parser.index) {
+            Some((position, token)) => Err(FilterError::UnexpectedToken {
+                position: *position,
+                found: token.to_string(),
+                expected: "and, or or the end of the filter",
+            }),
+            None => Ok(Filter(filter)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::card::fixtures::{self, BODY};
+    use std::collections::BTreeMap;
+
+    fn card() -> ProjectCard {
+        let mut card = fixtures::card("Login", BODY);
+        card.status = "In progress".to_string();
+        card.section = "Backend".to_string();
+        card.sub_section = "Accounts".to_string();
+        card.assignees = vec!["jdoe".to_string()];
+        card.assignee_names = BTreeMap::from([("jdoe".to_string(), "Jane Doe".to_string())]);
+        card.labels = vec!["bug".to_string(), "auth".to_string()];
+        card.state = Some(IssueState::Open);
+        card.item_type = ItemType::Issue;
+        card.fields = BTreeMap::from([
+            (
+                "Sprint".to_string(),
+                FieldValue::Iteration("Sprint 4".to_string()),
+            ),
+            (
+                "Priority".to_string(),
+                FieldValue::SingleSelect("P1".to_string()),
+            ),
+            ("Estimate".to_string(), FieldValue::Number(3.0)),
+        ]);
+        card
+    }
+
+    fn matches(expression: &str) -> bool {
+        expression.parse::<Filter>().unwrap().matches(&card())
+    }
+
+    #[test]
+    fn every_field_is_compared() {
+        assert!(matches("status == \"in progress\""));
+        assert!(matches("section == Backend"));
+        assert!(matches("sub_section == accounts"));
+        assert!(matches("assignee == jdoe"));
+        assert!(matches("assignee == \"Jane Doe\""));
+        assert!(matches("label == auth"));
+        assert!(matches("sprint == \"Sprint 4\""));
+        assert!(matches("iteration == \"sprint 4\""));
+        assert!(matches("state == open"));
+        assert!(matches("type == issue"));
+        assert!(matches("archived == false"));
+        assert!(matches("priority == p1"));
+        assert!(matches("Estimate == 3"));
+
+        assert!(!matches("state == closed"));
+        assert!(!matches("type == pr"));
+        assert!(!matches("archived == true"));
+        assert!(!matches("label == feature"));
+    }
+
+    #[test]
+    fn unknown_fields_never_match() {
+        assert!(!matches("milestone == v1"));
+        assert!(!matches("milestone ~ v"));
+        assert!(matches("milestone != v1"));
+    }
+
+    #[test]
+    fn operators() {
+        assert!(matches("status != done"));
+        assert!(!matches("status != \"In progress\""));
+        assert!(matches("label != feature"));
+        assert!(!matches("label != bug"));
+        assert!(matches("status ~ PROG"));
+        assert!(matches("sprint ~ 4"));
+        assert!(!matches("status ~ done"));
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_which_binds_tighter_than_or() {
+        assert!(matches(
+            "status == done or status == \"in progress\" and label == bug"
+        ));
+        assert!(!matches(
+            "(status == done or status == \"in progress\") and label == feature"
+        ));
+        assert!(matches(
+            "status == done or label == bug and not label == feature"
+        ));
+        assert!(!matches("not status == done and label == feature"));
+        assert!(matches("not (status == done and label == feature)"));
+        assert!(matches("not not label == bug"));
+        assert!(matches("status == done OR label == bug"));
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces_and_operators() {
+        let mut card = card();
+        card.status = "Won't do == never".to_string();
+
+        let filter: Filter = "status == \"won't do == never\"".parse().unwrap();
+        assert!(filter.matches(&card));
+
+        let filter: Filter = "status ~ 'do =='".parse().unwrap();
+        assert!(filter.matches(&card));
+    }
+
+    #[test]
+    fn errors_report_their_position() {
+        let error = "status == \"done".parse::<Filter>().unwrap_err();
+        assert!(matches!(error, FilterError::UnterminatedString(10)));
+
+        let error = "status == done and labels bug"
+            .parse::<Filter>()
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            FilterError::UnexpectedToken { position: 26, ref found, .. } if found == "bug"
+        ));
+
+        let error = "status = done".parse::<Filter>().unwrap_err();
+        assert!(matches!(
+            error,
+            FilterError::UnexpectedToken { position: 7, .. }
+        ));
+
+        let error = "(status == done".parse::<Filter>().unwrap_err();
+        assert!(matches!(
+            error,
+            FilterError::UnexpectedEnd { expected: ")" }
+        ));
+
+        let error = "status == done label == bug".parse::<Filter>().unwrap_err();
+        assert!(matches!(
+            error,
+            FilterError::UnexpectedToken { position: 15, ref found, .. } if found == "label"
+        ));
+    }
+}
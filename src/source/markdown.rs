@@ -0,0 +1,273 @@
+use async_trait::async_trait;
+use colored::Colorize;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    card::{FieldValue, ItemType, ProjectCard},
+    CardSource, SourceError,
+};
+
+const TOML_DELIMITER: &str = "+++";
+const YAML_DELIMITER: &str = "---";
+
+#[derive(Debug)]
+pub enum MarkdownError {
+    /// The cards directory or one of its files could not be read
+    Io(PathBuf, io::Error),
+    /// The front matter of a card is missing or invalid
+    FrontMatter(PathBuf, String),
+}
+
+impl Display for MarkdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkdownError::Io(path, e) => write!(f, "Reading {} failed : {}", path.display(), e),
+            MarkdownError::FrontMatter(path, e) => {
+                write!(f, "Invalid front matter in {} : {}", path.display(), e)
+            }
+        }
+    }
+}
+
+impl Error for MarkdownError {}
+
+/// Card metadata written at the top of each file, either as TOML between `+++` lines
+/// or as YAML between `---` lines
+#[derive(Deserialize)]
+struct FrontMatter {
+    title: Option<String>,
+    section: Option<String>,
+    #[serde(alias = "sub-section")]
+    sub_section: Option<String>,
+    status: Option<String>,
+    #[serde(alias = "working-days")]
+    working_days: Option<f32>,
+    #[serde(default)]
+    assignees: Vec<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    /// Titles or references of the cards this one depends on
+    #[serde(default, alias = "depends-on")]
+    depends_on: Vec<String>,
+    /// Any other key is exposed as a custom field
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// Card source reading one markdown file per card from a directory
+pub struct MarkdownSource {
+    dir: PathBuf,
+}
+
+impl MarkdownSource {
+    pub fn new(dir: &str) -> MarkdownSource {
+        MarkdownSource {
+            dir: PathBuf::from(dir),
+        }
+    }
+
+    fn read_card(path: &Path) -> Result<ProjectCard, MarkdownError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| MarkdownError::Io(path.to_path_buf(), e))?
+            .replace("\r\n", "\n");
+
+        let (front_matter, body) = split_front_matter(&content).ok_or_else(|| {
+            MarkdownError::FrontMatter(path.to_path_buf(), "front matter not found".to_string())
+        })?;
+
+        let front_matter: FrontMatter = match front_matter {
+            FrontMatterFormat::Toml(raw) => toml::from_str(raw).map_err(|e| e.to_string()),
+            FrontMatterFormat::Yaml(raw) => serde_yaml::from_str(raw).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| MarkdownError::FrontMatter(path.to_path_buf(), e))?;
+
+        let name = front_matter.title.unwrap_or_else(|| {
+            path.file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+
+        Ok(ProjectCard {
+            section: front_matter.section.unwrap_or_default(),
+            sub_section: front_matter.sub_section.unwrap_or_default(),
+            status: front_matter.status.unwrap_or_default(),
+            working_days: front_matter.working_days.unwrap_or_default(),
+            content: body.trim().to_string(),
+            assignees: front_matter.assignees,
+            assignee_names: BTreeMap::new(),
+            fields: front_matter
+                .extra
+                .into_iter()
+                .filter_map(|(key, value)| match value {
+                    serde_json::Value::String(text) => Some((key, FieldValue::Text(text))),
+                    serde_json::Value::Number(number) => {
+                        Some((key, FieldValue::Number(number.as_f64()?)))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            labels: front_matter.labels,
+            state: None,
+            item_type: ItemType::Draft,
+            archived: false,
+            url: Some(path.display().to_string()),
+            id: None,
+            references: Vec::new(),
+            repository: None,
+            dependencies: front_matter.depends_on,
+            linked_issues: Vec::new(),
+            name,
+        })
+    }
+}
+
+enum FrontMatterFormat<'a> {
+    Toml(&'a str),
+    Yaml(&'a str),
+}
+
+/// Splits a file into its front matter and its body
+fn split_front_matter(content: &str) -> Option<(FrontMatterFormat<'_>, &str)> {
+    let delimiter = [TOML_DELIMITER, YAML_DELIMITER]
+        .into_iter()
+        .find(|d| content.starts_with(&format!("{}\n", d)))?;
+
+    let rest = &content[delimiter.len() + 1..];
+    let end = rest.find(&format!("\n{}", delimiter))?;
+    let body = rest[end + 1 + delimiter.len()..].trim_start_matches(|c| c != '\n');
+
+    let raw = &rest[..end];
+    let format = if delimiter == TOML_DELIMITER {
+        FrontMatterFormat::Toml(raw)
+    } else {
+        FrontMatterFormat::Yaml(raw)
+    };
+
+    Some((format, body))
+}
+
+/// Prints the files skipped from the PLD the same way cards failing to parse are reported
+fn print_skipped(skipped: Vec<MarkdownError>) {
+    if skipped.is_empty() {
+        return;
+    }
+
+    println!(
+        "{} {} card(s) skipped due to unreadable files :",
+        "WARNING:".yellow(),
+        skipped.len()
+    );
+
+    for error in skipped {
+        println!("    - {}", error.to_string().red());
+    }
+}
+
+#[async_trait]
+impl CardSource for MarkdownSource {
+    fn name(&self) -> String {
+        format!("markdown directory {}", self.dir.display())
+    }
+
+    async fn validate(&self) -> Result<(), SourceError> {
+        if !self.dir.is_dir() {
+            return Err(SourceError::Markdown(MarkdownError::Io(
+                self.dir.clone(),
+                io::Error::new(io::ErrorKind::NotFound, "not a directory"),
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn get_cards(&self) -> Result<Vec<ProjectCard>, SourceError> {
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| SourceError::Markdown(MarkdownError::Io(self.dir.clone(), e)))?;
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        paths.sort();
+
+        // A malformed file only skips its own card
+        let (cards, skipped): (Vec<_>, Vec<_>) = paths
+            .iter()
+            .map(|path| Self::read_card(path))
+            .partition(Result::is_ok);
+
+        print_skipped(skipped.into_iter().filter_map(Result::err).collect());
+
+        Ok(cards.into_iter().filter_map(Result::ok).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/markdown");
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(FIXTURES).join(name)
+    }
+
+    #[test]
+    fn toml_front_matter() {
+        let card = MarkdownSource::read_card(&fixture("toml.md")).unwrap();
+
+        assert_eq!(card.name, "1.1 Protoboard");
+        assert_eq!(card.section, "Hardware");
+        assert_eq!(card.sub_section, "Electronics");
+        assert_eq!(card.status, "Done");
+        assert_eq!(card.working_days, 2.0);
+        assert_eq!(card.assignees, ["Nydragon"]);
+        assert_eq!(card.dependencies, ["1.2 Case"]);
+        assert!(matches!(&card.fields["Priority"], FieldValue::Text(text) if text == "High"));
+        assert!(card.content.starts_with("# User wish"));
+    }
+
+    #[test]
+    fn yaml_front_matter_with_dashed_keys() {
+        let card = MarkdownSource::read_card(&fixture("yaml.md")).unwrap();
+
+        // The title defaults to the file name
+        assert_eq!(card.name, "yaml");
+        assert_eq!(card.sub_section, "Case");
+        assert_eq!(card.working_days, 1.5);
+        assert_eq!(card.labels, ["print"]);
+        assert!(matches!(card.fields["Estimate"], FieldValue::Number(n) if n == 3.0));
+        assert!(card.content.ends_with("print the case"));
+    }
+
+    #[test]
+    fn invalid_front_matter_is_an_error() {
+        let error = MarkdownSource::read_card(&fixture("invalid.md")).unwrap_err();
+
+        assert!(
+            matches!(error, MarkdownError::FrontMatter(path, _) if path.ends_with("invalid.md"))
+        );
+    }
+
+    #[test]
+    fn missing_front_matter() {
+        assert!(split_front_matter("# User wish\n").is_none());
+        assert!(split_front_matter("+++\ntitle = \"a\"\n").is_none());
+    }
+
+    #[tokio::test]
+    async fn malformed_files_only_skip_their_card() {
+        let cards = MarkdownSource::new(FIXTURES).get_cards().await.unwrap();
+
+        let names: Vec<&str> = cards.iter().map(|card| card.name.as_str()).collect();
+        assert_eq!(names, ["1.1 Protoboard", "yaml"]);
+    }
+}
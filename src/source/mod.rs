@@ -0,0 +1,230 @@
+pub mod card;
+pub mod csv_file;
+pub mod filter;
+pub mod markdown;
+
+use async_trait::async_trait;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+    time::Duration,
+};
+
+use card::ProjectCard;
+use csv_file::{CsvError, CsvSource};
+use markdown::{MarkdownError, MarkdownSource};
+
+use crate::{
+    cache::CacheMode,
+    config::{Config, SourceKind},
+    github::{GithubError, ProjectsClient},
+    gitlab::{GitlabClient, GitlabError},
+    jira::{JiraClient, JiraError},
+};
+
+/// Time after which a request to a remote source is abandoned, so that a stalled server does not
+/// hang the run
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum SourceError {
+    /// The table of the selected source is missing from the config
+    MissingConfig(&'static str),
+    /// The source does not support updating its cards
+    ReadOnly(String),
+    Csv(CsvError),
+    Github(GithubError),
+    Gitlab(GitlabError),
+    Jira(JiraError),
+    Markdown(MarkdownError),
+}
+
+impl Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::MissingConfig(table) => write!(
+                f,
+                "The selected card source requires a [{}] table in the config",
+                table
+            ),
+            SourceError::ReadOnly(name) => write!(f, "Cards of {} cannot be updated", name),
+            SourceError::Csv(e) => write!(f, "{}", e),
+            SourceError::Github(e) => write!(f, "{}", e),
+            SourceError::Gitlab(e) => write!(f, "{}", e),
+            SourceError::Jira(e) => write!(f, "{}", e),
+            SourceError::Markdown(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SourceError {}
+
+/// Backend providing the cards of the PLD
+#[async_trait]
+pub trait CardSource: Send + Sync {
+    /// Short description of the source used in messages
+    fn name(&self) -> String;
+
+    /// Checks that the source is usable before fetching any card
+    async fn validate(&self) -> Result<(), SourceError> {
+        Ok(())
+    }
+
+    /// Fetches every card of the source
+    async fn get_cards(&self) -> Result<Vec<ProjectCard>, SourceError>;
+
+    /// Replaces the body of a card, sources which cannot be written to refuse every update
+    async fn update_body(&self, _card: &ProjectCard, _body: &str) -> Result<(), SourceError> {
+        Err(SourceError::ReadOnly(self.name()))
+    }
+
+    /// Prints information about the source once the PLD has been generated
+    fn report(&self) {}
+}
+
+/// Builds the card sources selected in the config, github may merge several projects
+pub fn from_config(
+    conf: &Config,
+    cache: CacheMode,
+) -> Result<Vec<Box<dyn CardSource>>, SourceError> {
+    let source: Box<dyn CardSource> = match conf.source {
+        SourceKind::Csv => {
+            let csv = conf.csv.as_ref().ok_or(SourceError::MissingConfig("csv"))?;
+
+            Box::new(CsvSource::new(csv))
+        }
+        SourceKind::Github => {
+            let github = conf
+                .github
+                .as_ref()
+                .ok_or(SourceError::MissingConfig("github"))?;
+
+            let projects = github.all_projects();
+            if projects.is_empty() {
+                return Err(SourceError::MissingConfig("github.projects"));
+            }
+
+            return Ok(projects
+                .iter()
+                .map(|project| {
+                    Box::new(ProjectsClient::new(github, project, cache.clone()))
+                        as Box<dyn CardSource>
+                })
+                .collect());
+        }
+        SourceKind::Gitlab => {
+            let gitlab = conf
+                .gitlab
+                .as_ref()
+                .ok_or(SourceError::MissingConfig("gitlab"))?;
+
+            Box::new(GitlabClient::new(gitlab, cache))
+        }
+        SourceKind::Jira => {
+            let jira = conf
+                .jira
+                .as_ref()
+                .ok_or(SourceError::MissingConfig("jira"))?;
+
+            Box::new(JiraClient::new(jira, cache))
+        }
+        SourceKind::Markdown => {
+            let markdown = conf
+                .markdown
+                .as_ref()
+                .ok_or(SourceError::MissingConfig("markdown"))?;
+
+            Box::new(MarkdownSource::new(&markdown.path))
+        }
+    };
+
+    Ok(vec![source])
+}
+
+/// Cards of every source merged together
+#[derive(Default)]
+pub struct MergedCards {
+    pub cards: Vec<ProjectCard>,
+    /// Name of the source of each card, in the order of `cards`
+    pub sources: Vec<String>,
+}
+
+impl MergedCards {
+    /// Titles of the cards `kept` which are found in several sources, with the name of each
+    /// source, titles repeated within a single source are not reported
+    pub fn duplicates(&self, kept: impl Fn(&ProjectCard) -> bool) -> BTreeMap<String, Vec<String>> {
+        let mut origins: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for (card, source) in self.cards.iter().zip(&self.sources) {
+            if !kept(card) {
+                continue;
+            }
+
+            let card_origins = origins.entry(card.name.trim().to_string()).or_default();
+            if !card_origins.contains(source) {
+                card_origins.push(source.clone());
+            }
+        }
+
+        origins.retain(|_, card_origins| card_origins.len() > 1);
+        origins
+    }
+}
+
+/// Fetches the cards of every source, keeping track of the source of each card
+pub async fn merge_cards(sources: &[Box<dyn CardSource>]) -> Result<MergedCards, SourceError> {
+    let mut merged = MergedCards::default();
+
+    for source in sources {
+        let source_cards = source.get_cards().await?;
+
+        merged
+            .sources
+            .extend(std::iter::repeat_n(source.name(), source_cards.len()));
+        merged.cards.extend(source_cards);
+    }
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::card::fixtures::{self, BODY};
+
+    fn merged(cards: &[(&str, &str)]) -> MergedCards {
+        MergedCards {
+            cards: cards
+                .iter()
+                .map(|(_, name)| fixtures::card(name, BODY))
+                .collect(),
+            sources: cards.iter().map(|(source, _)| source.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn duplicates_are_titles_shared_by_several_sources() {
+        let merged = merged(&[
+            ("front", "Login"),
+            ("back", "Login "),
+            ("back", "Signup"),
+            ("back", "Signup"),
+        ]);
+
+        let duplicates = merged.duplicates(|_| true);
+
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates["Login"], vec!["front", "back"]);
+    }
+
+    #[test]
+    fn duplicates_ignore_the_cards_left_out() {
+        let mut merged = merged(&[("front", "Login"), ("back", "Login")]);
+        merged.cards[0].working_days = 1.0;
+
+        let duplicates = merged.duplicates(|card| card.working_days != 0.0);
+
+        assert!(duplicates.is_empty());
+    }
+}